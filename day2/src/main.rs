@@ -1,49 +1,218 @@
-// A, X == rock, B, Y == paper, C, Z = scissors
-
-// A beats Z, // X beats C
-// B beats X, // Y beats A
-// C beats Y, // Z beats B
-
-fn main() {
-    let input = include_str!("../input");
-
-    // PART 1
-    let mut score = 0;
-    for line in input.lines() {
-        match line {
-            "B X" => score += 1,
-            "C Y" => score += 2,
-            "A Z" => score += 3,
-            "A X" => score += 4,
-            "B Y" => score += 5,
-            "C Z" => score += 6,
-            "C X" => score += 7,
-            "A Y" => score += 8,
-            "B Z" => score += 9,
-            _ => {
-                panic!("Line: '{:?}'. This shouldn't happen ...", line)
-            }
+mod optimiser;
+mod rules;
+mod tournament;
+
+use color_eyre::eyre::{eyre, Report};
+use optimiser::Constraints;
+use rules::RuleSet;
+
+// Each line is the opponent's letter followed by our letter, both mapped by the rule set
+fn parse_round(line: &str) -> Result<(&str, &str), Report> {
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(theirs), Some(ours), None) => Ok((theirs, ours)),
+        _ => Err(eyre!("Line: '{line}'. Expected two letters")),
+    }
+}
+
+// The second column is the shape we play, returns (theirs, ours) for each round
+fn parse_guide(input: &str, rules: &RuleSet) -> Result<Vec<(usize, usize)>, Report> {
+    input
+        .lines()
+        .map(|line| {
+            let (theirs, ours) = parse_round(line)?;
+            let theirs = rules
+                .opponent_shape(theirs)
+                .ok_or_else(|| eyre!("Line: '{line}'. Unknown opponent shape"))?;
+            let ours = rules
+                .our_shape(ours)
+                .ok_or_else(|| eyre!("Line: '{line}'. Unknown shape"))?;
+            Ok((theirs, ours))
+        })
+        .collect()
+}
+
+fn part1(input: &str, rules: &RuleSet) -> Result<u32, Report> {
+    Ok(parse_guide(input, rules)?
+        .into_iter()
+        .map(|(theirs, ours)| rules.score(ours, theirs))
+        .sum())
+}
+
+// The second column is how the round needs to end, returns (theirs, ours) for each round
+fn decrypt_guide(input: &str, rules: &RuleSet) -> Result<Vec<(usize, usize)>, Report> {
+    input
+        .lines()
+        .map(|line| {
+            let (theirs, outcome) = parse_round(line)?;
+            let theirs = rules
+                .opponent_shape(theirs)
+                .ok_or_else(|| eyre!("Line: '{line}'. Unknown opponent shape"))?;
+            let outcome = rules
+                .wanted_outcome(outcome)
+                .ok_or_else(|| eyre!("Line: '{line}'. Unknown outcome"))?;
+            let ours = rules
+                .shape_for(theirs, outcome)
+                .ok_or_else(|| eyre!("Line: '{line}'. No shape gives {outcome:?}"))?;
+            Ok((theirs, ours))
+        })
+        .collect()
+}
+
+fn part2(input: &str, rules: &RuleSet) -> Result<u32, Report> {
+    Ok(decrypt_guide(input, rules)?
+        .into_iter()
+        .map(|(theirs, ours)| rules.score(ours, theirs))
+        .sum())
+}
+
+// Print every round of both parts with a summary of each, as text or CSV
+fn log(input: &str, rules: &RuleSet, csv: bool) -> Result<(), Report> {
+    let part1 = tournament::play(rules, &parse_guide(input, rules)?);
+    let part2 = tournament::play(rules, &decrypt_guide(input, rules)?);
+
+    if csv {
+        print!(
+            "{}",
+            tournament::to_csv(rules, &[("1", &part1), ("2", &part2)])
+        );
+    } else {
+        println!("{}", tournament::to_text(rules, "Part 1", &part1));
+        print!("{}", tournament::to_text(rules, "Part 2", &part2));
+    }
+    Ok(())
+}
+
+// Compare the provided guide against the best guide for the same opponent
+fn optimise(
+    input: &str,
+    rules: &RuleSet,
+    constraints: &Constraints,
+    print_guide: bool,
+) -> Result<(), Report> {
+    let guide = parse_guide(input, rules)?;
+    let opponent: Vec<usize> = guide.iter().map(|&(theirs, _)| theirs).collect();
+    let best = optimiser::optimise(rules, &opponent, constraints)
+        .ok_or_else(|| eyre!("No guide satisfies the constraints"))?;
+
+    println!("Provided guide score: {}", part1(input, rules)?);
+    if best.exact {
+        println!("Optimal guide score: {}", best.score);
+    } else {
+        println!(
+            "Best guide score found: {} (may not be optimal)",
+            best.score
+        );
+    }
+    if print_guide {
+        for (&theirs, &ours) in opponent.iter().zip(&best.guide) {
+            println!(
+                "{} {}",
+                rules.shapes[theirs].opponent_letter, rules.shapes[ours].our_letter
+            );
         }
     }
-    println!("Part 1: Final score: {}", score);
-
-    // PART 2
-    let mut score = 0;
-    for line in input.lines() {
-        match line {
-            "B X" => score += 1, // X
-            "C X" => score += 2, // Y
-            "A X" => score += 3, // Z
-            "A Y" => score += 4, // X
-            "B Y" => score += 5, // Y
-            "C Y" => score += 6, // Z
-            "C Z" => score += 7, // X
-            "A Z" => score += 8, // Y
-            "B Z" => score += 9, // Z
-            _ => {
-                panic!("Line: '{:?}'. This shouldn't happen ...", line)
+    Ok(())
+}
+
+// Usage: day2 [--rules classic|rpsls|<file>] [--log text|csv] [--optimise
+//             [--shape-limits n,n,..] [--min-losses n] [--print-guide]] [input file]
+fn main() -> Result<(), Report> {
+    color_eyre::install()?;
+    let mut rules = RuleSet::classic();
+    let mut input = include_str!("../input").to_string();
+    let mut optimise_guide = false;
+    let mut log_format = None;
+    let mut print_guide = false;
+    let mut constraints = Constraints::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => {
+                rules = match args.next().as_deref() {
+                    Some("classic") => RuleSet::classic(),
+                    Some("rpsls") => RuleSet::rpsls(),
+                    Some(path) => RuleSet::parse(&std::fs::read_to_string(path)?)?,
+                    None => return Err(eyre!("--rules needs a rule set name or file")),
+                }
+            }
+            "--log" => {
+                log_format = match args.next().as_deref() {
+                    Some("text") => Some(false),
+                    Some("csv") => Some(true),
+                    _ => return Err(eyre!("--log needs 'text' or 'csv'")),
+                }
+            }
+            "--optimise" => optimise_guide = true,
+            "--print-guide" => print_guide = true,
+            "--shape-limits" => {
+                let limits = args
+                    .next()
+                    .ok_or_else(|| eyre!("--shape-limits needs a count for each shape"))?;
+                constraints.shape_limits = Some(
+                    limits
+                        .split(',')
+                        .map(|n| n.parse())
+                        .collect::<Result<_, _>>()?,
+                );
             }
-        };
+            "--min-losses" => {
+                constraints.min_losses = args
+                    .next()
+                    .ok_or_else(|| eyre!("--min-losses needs a number"))?
+                    .parse()?;
+            }
+            path => input = std::fs::read_to_string(path)?,
+        }
+    }
+
+    if let Some(limits) = &constraints.shape_limits {
+        // the rule set may come after the limits
+        if limits.len() != rules.shapes.len() {
+            return Err(eyre!(
+                "--shape-limits needs {} counts, one for each shape, got {}",
+                rules.shapes.len(),
+                limits.len()
+            ));
+        }
+    }
+    if optimise_guide {
+        return optimise(&input, &rules, &constraints, print_guide);
+    }
+    if let Some(csv) = log_format {
+        return log(&input, &rules, csv);
+    }
+    println!("Part 1: Final score: {}", part1(&input, &rules)?);
+    println!("Part 2: Final score: {}", part2(&input, &rules)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &str = "A Y
+B X
+C Z";
+    const INPUT: &str = include_str!("../input");
+
+    #[test]
+    fn classic() {
+        let rules = RuleSet::classic();
+        assert_eq!(part1(TEST_INPUT, &rules).unwrap(), 15);
+        assert_eq!(part2(TEST_INPUT, &rules).unwrap(), 12);
+        assert_eq!(part1(INPUT, &rules).unwrap(), 8392);
+        assert_eq!(part2(INPUT, &rules).unwrap(), 10116);
+    }
+
+    #[test]
+    fn rpsls() {
+        let rules = RuleSet::rpsls();
+        // Spock vs Lizard: 5 + 0, Lizard vs Spock: 4 + 6, Rock vs Rock: 1 + 3
+        assert_eq!(part1("D V\nE W\nA X", &rules).unwrap(), 19);
+        // Beat Scissors with Spock, lose to Spock with Scissors
+        assert_eq!(part2("C Z\nE X", &rules).unwrap(), 5 + 6 + 3);
+        assert!(part1("F X", &rules).is_err());
     }
-    println!("Part 2: Final score: {}", score);
 }
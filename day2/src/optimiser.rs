@@ -0,0 +1,722 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::rules::{Outcome, RuleSet};
+
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    // The most times we may play each shape, in rule set order
+    pub shape_limits: Option<Vec<usize>>,
+    // The least number of rounds we have to lose
+    pub min_losses: usize,
+}
+
+#[derive(Debug)]
+pub struct Optimised {
+    pub score: u32,
+    // The shape we play in each round
+    pub guide: Vec<usize>,
+    // Whether no other guide can score more
+    pub exact: bool,
+}
+
+// Above this many ways of spreading the required losses over the opponent
+// shapes, trying each of them takes too long
+const MAX_SPLITS: usize = 10_000;
+
+// Find the guide with the highest score against the opponent's shapes, a
+// min cost flow from opponent shapes to our shapes. A loss requirement breaks
+// the flow model, so instead each loss earns a bonus, the smallest one for
+// which the best guides lose often enough. Starting from the best guide losing
+// least with that bonus, plays are then swapped around until it loses often
+// enough. The bonus also bounds the best score, and when the guide found
+// doesn't reach that bound, every way of spreading the losses over the
+// opponent shapes is tried instead if there aren't too many.
+pub fn optimise(
+    rules: &RuleSet,
+    opponent: &[usize],
+    constraints: &Constraints,
+) -> Option<Optimised> {
+    let n = rules.shapes.len();
+    let mut counts = vec![0; n];
+    for &theirs in opponent {
+        counts[theirs] += 1;
+    }
+    let limits = match &constraints.shape_limits {
+        Some(limits) => limits.clone(),
+        None => vec![opponent.len(); n],
+    };
+    assert_eq!(limits.len(), n, "a limit is needed for every shape");
+    let min_losses = constraints.min_losses;
+    if min_losses > opponent.len() {
+        return None;
+    }
+
+    let flow = Flow::new(rules, &counts, &limits);
+    let (mut plays, bound) = flow.with_bonus(min_losses)?;
+    let mut exact = plays
+        .as_ref()
+        .is_some_and(|plays| (flow.score(plays) as i64 + 1) * flow.steps > bound);
+    if !exact && flow.splits(min_losses) <= MAX_SPLITS {
+        plays = Some(flow.every_split(min_losses)?);
+        exact = true;
+    }
+    let mut plays = plays?;
+
+    let score = flow.score(&plays);
+    let guide = opponent
+        .iter()
+        .map(|&theirs| {
+            let ours = plays[theirs].iter().position(|&p| p > 0).unwrap();
+            plays[theirs][ours] -= 1;
+            ours
+        })
+        .collect();
+    Some(Optimised {
+        score,
+        guide,
+        exact,
+    })
+}
+
+// Which of the guides with the same score and bonus to pick
+#[derive(Clone, Copy)]
+enum Ties {
+    FewerLosses,
+    MoreLosses,
+}
+
+// The flow model of a game: opponent shapes, each with as many rounds as it's
+// played, sending them to our shapes, each taking at most its limit
+struct Flow<'a> {
+    rules: &'a RuleSet,
+    counts: &'a [usize],
+    limits: &'a [usize],
+    // The best possible round, to keep costs low
+    max_score: u32,
+    // Bonuses are in fractions of a point. Swapping plays around changes
+    // the number of losses by at most the number of shapes, so with every
+    // such denominator the bonus can hit any point where the best guides
+    // change.
+    steps: i64,
+    // Cost of a step, outweighing any number of tie breaks
+    scale: i64,
+}
+
+// A solved flow, with the edge carrying each pair of shapes
+struct Solved {
+    graph: Graph,
+    pair_edges: Vec<(usize, usize, usize)>,
+}
+
+impl<'a> Flow<'a> {
+    fn new(rules: &'a RuleSet, counts: &'a [usize], limits: &'a [usize]) -> Self {
+        let max_score = rules.shapes.iter().map(|s| s.score).max().unwrap_or(0)
+            + Outcome::ALL
+                .into_iter()
+                .map(|o| rules.outcome_score(o))
+                .max()
+                .unwrap_or(0);
+        // Beyond a handful of shapes the steps only get close to those points
+        let lcm = |a: i64, b: i64| a / gcd(a, b) * b;
+        let steps = (1..=counts.len() as i64)
+            .try_fold(1, |steps, k| Some(lcm(steps, k)).filter(|&l| l <= 1 << 20))
+            .unwrap_or(1 << 20);
+        let total: usize = counts.iter().sum();
+        Flow {
+            rules,
+            counts,
+            limits,
+            max_score,
+            steps,
+            scale: (total + counts.len() + 1) as i64,
+        }
+    }
+
+    // A bonus beyond the score of any round
+    fn max_bonus(&self) -> i64 {
+        (self.max_score as i64 + 1) * self.steps
+    }
+
+    fn lost(&self, s: usize, t: usize) -> i64 {
+        (self.rules.outcome(s, t) == Outcome::Lose) as i64
+    }
+
+    // Cost of playing s against t, kept low by counting points below the
+    // best possible round, with the bonus taken off
+    fn points(&self, s: usize, t: usize, bonus: i64) -> i64 {
+        let below = (self.max_score - self.rules.score(s, t)) as i64;
+        self.scale * (below * self.steps - bonus * self.lost(s, t))
+    }
+
+    fn cost(&self, s: usize, t: usize, bonus: i64, ties: Ties) -> i64 {
+        let lost = self.lost(s, t);
+        match ties {
+            Ties::FewerLosses => self.points(s, t, bonus) + lost,
+            Ties::MoreLosses => self.points(s, t, bonus) - lost,
+        }
+    }
+
+    fn solve(&self, bonus: i64, ties: Ties) -> Option<Solved> {
+        let n = self.counts.len();
+        // source, n opponent shape nodes, n shape nodes, sink
+        let source = 0;
+        let theirs = |t: usize| 1 + t;
+        let ours = |s: usize| 1 + n + s;
+        let sink = 1 + 2 * n;
+        let mut graph = Graph::new(sink + 1);
+
+        let mut pair_edges = vec![];
+        for t in 0..n {
+            graph.add_edge(source, theirs(t), self.counts[t], 0);
+            for s in 0..n {
+                let cost = self.cost(s, t, bonus, ties);
+                pair_edges.push((t, s, graph.add_edge(theirs(t), ours(s), usize::MAX, cost)));
+            }
+        }
+        for (s, &limit) in self.limits.iter().enumerate() {
+            graph.add_edge(ours(s), sink, limit, 0);
+        }
+
+        let (flow, _) = graph.min_cost_flow(source, sink);
+        (flow == self.counts.iter().sum()).then_some(Solved { graph, pair_edges })
+    }
+
+    // Number of times we play each shape against each opponent shape
+    fn plays(&self, solved: &Solved) -> Vec<Vec<usize>> {
+        let n = self.counts.len();
+        let mut plays = vec![vec![0; n]; n];
+        for &(t, s, edge) in &solved.pair_edges {
+            plays[t][s] += solved.graph.flow(edge);
+        }
+        plays
+    }
+
+    fn losses(&self, plays: &[Vec<usize>]) -> usize {
+        let n = self.counts.len();
+        (0..n)
+            .flat_map(|t| (0..n).map(move |s| (t, s)))
+            .filter(|&(t, s)| self.lost(s, t) == 1)
+            .map(|(t, s)| plays[t][s])
+            .sum()
+    }
+
+    // The guide found with a bonus for each loss, none if no guide loses
+    // often enough, along with a bound on the best score in steps. The guide
+    // is missing in the unlikely case swapping plays around gets stuck.
+    fn with_bonus(&self, min_losses: usize) -> Option<(Option<Vec<Vec<usize>>>, i64)> {
+        let best = |bonus, ties| {
+            let plays = self.plays(&self.solve(bonus, ties)?);
+            let losses = self.losses(&plays);
+            // no guide losing often enough scores more with the bonus
+            let bound = self.score(&plays) as i64 * self.steps
+                + bonus * (losses as i64 - min_losses as i64);
+            Some((losses >= min_losses, bound))
+        };
+        // With a bonus worth more than any round every possible loss is taken
+        let (mut low, mut high) = (0, self.max_bonus());
+        let (enough, mut bound) = best(high, Ties::MoreLosses)?;
+        if !enough {
+            return None;
+        }
+        let (enough, low_bound) = best(low, Ties::MoreLosses)?;
+        if enough {
+            high = low;
+        }
+        bound = bound.min(low_bound);
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            let (enough, mid_bound) = best(mid, Ties::MoreLosses)?;
+            bound = bound.min(mid_bound);
+            if enough {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        let mut solved = self.solve(high, Ties::FewerLosses)?;
+        self.add_losses(&mut solved, high, min_losses);
+        let plays = self.plays(&solved);
+        Some(((self.losses(&plays) >= min_losses).then_some(plays), bound))
+    }
+
+    fn score(&self, plays: &[Vec<usize>]) -> u32 {
+        let n = self.counts.len();
+        (0..n)
+            .flat_map(|t| (0..n).map(move |s| (t, s)))
+            .map(|(t, s)| plays[t][s] as u32 * self.rules.score(s, t))
+            .sum()
+    }
+
+    // Ways of spreading the losses over the opponent shapes, counted up to
+    // one past MAX_SPLITS
+    fn splits(&self, min_losses: usize) -> usize {
+        // ways[l] spreads l losses over the shapes so far
+        let mut ways = vec![0; min_losses + 1];
+        ways[0] = 1;
+        for &count in self.counts {
+            let before = ways.clone();
+            for (l, ways) in ways.iter_mut().enumerate() {
+                *ways = (l.saturating_sub(count)..=l)
+                    .map(|from| before[from])
+                    .fold(0, |sum: usize, w| sum.saturating_add(w).min(MAX_SPLITS + 1));
+            }
+        }
+        ways[min_losses]
+    }
+
+    // The best guide found by trying every way of spreading the losses over
+    // the opponent shapes
+    fn every_split(&self, min_losses: usize) -> Option<Vec<Vec<usize>>> {
+        let mut best: Option<Vec<Vec<usize>>> = None;
+        let mut losses = vec![0; self.counts.len()];
+        spread_losses(self.counts, min_losses, 0, &mut losses, &mut |losses| {
+            if let Some(plays) = self.split_plays(losses) {
+                if best
+                    .as_ref()
+                    .is_none_or(|b| self.score(&plays) > self.score(b))
+                {
+                    best = Some(plays);
+                }
+            }
+        });
+        best
+    }
+
+    // The best guide losing at least losses[t] of the rounds against
+    // opponent shape t
+    fn split_plays(&self, losses: &[usize]) -> Option<Vec<Vec<usize>>> {
+        let n = self.counts.len();
+        // source, n "any outcome" nodes, n "must lose" nodes, n shape nodes, sink
+        let source = 0;
+        let any = |t: usize| 1 + t;
+        let lose = |t: usize| 1 + n + t;
+        let ours = |s: usize| 1 + 2 * n + s;
+        let sink = 1 + 3 * n;
+        let mut graph = Graph::new(sink + 1);
+
+        let mut pair_edges = vec![];
+        for (t, &must_lose) in losses.iter().enumerate() {
+            graph.add_edge(source, any(t), self.counts[t] - must_lose, 0);
+            graph.add_edge(source, lose(t), must_lose, 0);
+            for s in 0..n {
+                let cost = self.points(s, t, 0);
+                pair_edges.push((t, s, graph.add_edge(any(t), ours(s), usize::MAX, cost)));
+                if self.lost(s, t) == 1 {
+                    pair_edges.push((t, s, graph.add_edge(lose(t), ours(s), usize::MAX, cost)));
+                }
+            }
+        }
+        for (s, &limit) in self.limits.iter().enumerate() {
+            graph.add_edge(ours(s), sink, limit, 0);
+        }
+
+        let (flow, _) = graph.min_cost_flow(source, sink);
+        if flow < self.counts.iter().sum() {
+            return None;
+        }
+        Some(self.plays(&Solved { graph, pair_edges }))
+    }
+
+    // Swap plays around until the guide loses often enough. Swaps keeping the
+    // score with the bonus come first, as long as they don't lose more often
+    // than needed, and then the one costing the fewest points to lose often
+    // enough.
+    fn add_losses(&self, solved: &mut Solved, bonus: i64, min_losses: usize) {
+        let graph = &mut solved.graph;
+        let mut gains = vec![0; graph.edges.len()];
+        for &(t, s, edge) in &solved.pair_edges {
+            graph.set_cost(edge, self.points(s, t, bonus));
+            gains[edge] = self.lost(s, t);
+            gains[edge ^ 1] = -self.lost(s, t);
+        }
+        let per_loss = self.scale * bonus;
+        let potentials = graph.potentials();
+        let mut losses = self.losses(&self.plays(solved)) as i64;
+        while losses < min_losses as i64 {
+            let needed = min_losses as i64 - losses;
+            let walks = solved.graph.cheapest_walks(&potentials, &gains);
+            let keeping = walks
+                .iter()
+                .filter(|(gain, cost, _)| *gain <= needed && *cost == 0)
+                .max_by_key(|(gain, _, _)| *gain);
+            let enough = walks
+                .iter()
+                .filter(|(gain, _, _)| *gain >= needed)
+                .min_by_key(|(gain, cost, _)| cost + per_loss * gain);
+            let Some((gain, _, walk)) = keeping.or(enough) else {
+                return;
+            };
+            solved.graph.push(walk);
+            losses += gain;
+        }
+    }
+}
+
+// Call f with every split of `remaining` losses over the opponent shapes
+fn spread_losses(
+    counts: &[usize],
+    remaining: usize,
+    shape: usize,
+    losses: &mut [usize],
+    f: &mut impl FnMut(&[usize]),
+) {
+    if shape == counts.len() - 1 {
+        if remaining <= counts[shape] {
+            losses[shape] = remaining;
+            f(losses);
+        }
+        return;
+    }
+    for n in 0..=remaining.min(counts[shape]) {
+        losses[shape] = n;
+        spread_losses(counts, remaining - n, shape + 1, losses, f);
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+struct Edge {
+    to: usize,
+    capacity: usize,
+    cost: i64,
+}
+
+// A small min cost flow network solved with successive shortest paths
+struct Graph {
+    edges: Vec<Edge>,
+    adjacent: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new(nodes: usize) -> Self {
+        Graph {
+            edges: vec![],
+            adjacent: vec![vec![]; nodes],
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: usize, cost: i64) -> usize {
+        let id = self.edges.len();
+        self.edges.push(Edge { to, capacity, cost });
+        self.adjacent[from].push(id);
+        self.edges.push(Edge {
+            to: from,
+            capacity: 0,
+            cost: -cost,
+        });
+        self.adjacent[to].push(id + 1);
+        id
+    }
+
+    fn set_cost(&mut self, edge: usize, cost: i64) {
+        self.edges[edge].cost = cost;
+        self.edges[edge ^ 1].cost = -cost;
+    }
+
+    // Shortest distances from anywhere, with which no edge with capacity left
+    // costs less than its ends differ by
+    fn potentials(&self) -> Vec<i64> {
+        let mut dist = vec![0; self.adjacent.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in 0..self.adjacent.len() {
+                for &id in &self.adjacent[node] {
+                    let edge = &self.edges[id];
+                    if edge.capacity > 0 && dist[node] + edge.cost < dist[edge.to] {
+                        dist[edge.to] = dist[node] + edge.cost;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        dist
+    }
+
+    // For each total gain, the cheapest closed walk whose edges' gains add up
+    // to it, as (gain, cost, edges). Costs are taken relative to potentials,
+    // so that they are never negative.
+    fn cheapest_walks(&self, potentials: &[i64], gains: &[i64]) -> Vec<(i64, i64, Vec<usize>)> {
+        let nodes = self.adjacent.len();
+        // gains along the way are kept within as many edges as there are nodes
+        let reach = nodes as i64;
+        let state = |node: usize, gain: i64| node * (2 * nodes + 1) + (gain + reach) as usize;
+        let mut best: Vec<Option<(i64, Vec<usize>)>> = vec![None; nodes + 1];
+        for start in 0..nodes {
+            let mut dist = vec![i64::MAX; nodes * (2 * nodes + 1)];
+            let mut via = vec![None; dist.len()];
+            let mut queue = BinaryHeap::from([Reverse((0, start, 0))]);
+            dist[state(start, 0)] = 0;
+            while let Some(Reverse((d, node, gain))) = queue.pop() {
+                if d > dist[state(node, gain)] {
+                    continue;
+                }
+                for &id in &self.adjacent[node] {
+                    let edge = &self.edges[id];
+                    let next = gain + gains[id];
+                    if edge.capacity == 0 || next.abs() > reach {
+                        continue;
+                    }
+                    let d = d + edge.cost + potentials[node] - potentials[edge.to];
+                    if d < dist[state(edge.to, next)] {
+                        dist[state(edge.to, next)] = d;
+                        via[state(edge.to, next)] = Some((id, node, gain));
+                        queue.push(Reverse((d, edge.to, next)));
+                    }
+                }
+            }
+            for gain in 1..=reach {
+                let d = dist[state(start, gain)];
+                if best[gain as usize].as_ref().is_some_and(|(b, _)| *b <= d) || d == i64::MAX {
+                    continue;
+                }
+                let mut walk = vec![];
+                let (mut node, mut g) = (start, gain);
+                while let Some((id, from, from_gain)) = via[state(node, g)] {
+                    walk.push(id);
+                    (node, g) = (from, from_gain);
+                    if (node, g) == (start, 0) {
+                        break;
+                    }
+                }
+                // a walk going through an edge more than once may not fit
+                let fits = walk.iter().all(|id| {
+                    self.edges[*id].capacity >= walk.iter().filter(|&other| other == id).count()
+                });
+                if fits {
+                    best[gain as usize] = Some((d, walk));
+                }
+            }
+        }
+        best.into_iter()
+            .enumerate()
+            .filter_map(|(gain, walk)| walk.map(|(cost, walk)| (gain as i64, cost, walk)))
+            .collect()
+    }
+
+    // Send one unit along each of the edges
+    fn push(&mut self, walk: &[usize]) {
+        for &id in walk {
+            self.edges[id].capacity -= 1;
+            self.edges[id ^ 1].capacity += 1;
+        }
+    }
+
+    // Flow through an edge is what's left on its reverse edge
+    fn flow(&self, edge: usize) -> usize {
+        self.edges[edge ^ 1].capacity
+    }
+
+    fn min_cost_flow(&mut self, source: usize, sink: usize) -> (usize, i64) {
+        let (mut flow, mut cost) = (0, 0);
+        loop {
+            // Bellman-Ford, as reverse edges have negative costs
+            let mut dist = vec![i64::MAX; self.adjacent.len()];
+            let mut via = vec![None; self.adjacent.len()];
+            dist[source] = 0;
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for node in 0..self.adjacent.len() {
+                    if dist[node] == i64::MAX {
+                        continue;
+                    }
+                    for &id in &self.adjacent[node] {
+                        let edge = &self.edges[id];
+                        if edge.capacity > 0 && dist[node] + edge.cost < dist[edge.to] {
+                            dist[edge.to] = dist[node] + edge.cost;
+                            via[edge.to] = Some(id);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+            if dist[sink] == i64::MAX {
+                return (flow, cost);
+            }
+
+            let mut path = vec![];
+            let mut node = sink;
+            while let Some(id) = via[node] {
+                path.push(id);
+                node = self.edges[id ^ 1].to;
+            }
+            let amount = path
+                .iter()
+                .map(|&id| self.edges[id].capacity)
+                .min()
+                .unwrap();
+            for &id in &path {
+                self.edges[id].capacity -= amount;
+                self.edges[id ^ 1].capacity += amount;
+            }
+            flow += amount;
+            cost += amount as i64 * dist[sink];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn unconstrained_wins_every_round() {
+        let rules = RuleSet::classic();
+        let best = optimise(&rules, &[0, 1, 2], &Constraints::default()).unwrap();
+        assert_eq!(best.guide, vec![1, 2, 0]);
+        assert_eq!(best.score, 8 + 9 + 7);
+    }
+
+    #[test]
+    fn constrained() {
+        let rules = RuleSet::classic();
+        let opponent = [0, 0, 0, 1];
+
+        // Only one Paper allowed, so two of the Rocks get a Rock (draw) instead
+        let limits = Constraints {
+            shape_limits: Some(vec![4, 1, 4]),
+            min_losses: 0,
+        };
+        let best = optimise(&rules, &opponent, &limits).unwrap();
+        assert_eq!(best.score, 8 + 4 + 4 + 9);
+
+        // Losing with Scissors against Rock costs 5 points, Rock against Paper costs 8
+        let losses = Constraints {
+            shape_limits: None,
+            min_losses: 1,
+        };
+        let best = optimise(&rules, &opponent, &losses).unwrap();
+        assert_eq!(best.score, 8 + 8 + 3 + 9);
+
+        let impossible = Constraints {
+            shape_limits: Some(vec![1, 1, 1]),
+            min_losses: 0,
+        };
+        assert!(optimise(&rules, &opponent, &impossible).is_none());
+    }
+
+    #[test]
+    fn losses_costing_more_than_the_bonus() {
+        let rules = RuleSet::classic();
+        // 2 Rocks, 5 Papers and 5 Scissors. Two losses cost 5 points each and
+        // the next three 18 together, but a third loss on its own costs 8.
+        let opponent = [2, 0, 2, 2, 1, 1, 1, 2, 2, 1, 1, 0];
+        let constraints = Constraints {
+            shape_limits: Some(vec![8, 2, 6]),
+            min_losses: 3,
+        };
+        let best = optimise(&rules, &opponent, &constraints).unwrap();
+        assert_eq!(best.score, 96 - 5 - 5 - 8);
+    }
+
+    #[test]
+    fn many_rounds() {
+        let rules = RuleSet::rpsls();
+        let opponent: Vec<usize> = (0..2500).map(|i| i * 7 % 5).collect();
+        let constraints = Constraints {
+            shape_limits: None,
+            min_losses: 100,
+        };
+        let best = optimise(&rules, &opponent, &constraints).unwrap();
+        let losses = best
+            .guide
+            .iter()
+            .zip(&opponent)
+            .filter(|&(&ours, &theirs)| rules.outcome(ours, theirs) == Outcome::Lose)
+            .count();
+        assert_eq!(losses, 100);
+    }
+
+    #[test]
+    fn swaps_getting_stuck() {
+        let rules = RuleSet::rpsls();
+        // Scissors, Paper, Rock with no Rock or Lizard to play
+        let constraints = Constraints {
+            shape_limits: Some(vec![0, 1, 2, 0, 1]),
+            min_losses: 2,
+        };
+        let best = optimise(&rules, &[2, 1, 0], &constraints).unwrap();
+        assert_eq!(best.score, 6 + 5 + 3);
+        assert!(best.exact);
+    }
+
+    // The best score of every guide meeting the constraints, trying them all
+    fn brute_force(rules: &RuleSet, opponent: &[usize], constraints: &Constraints) -> Option<u32> {
+        let n = rules.shapes.len();
+        (0..n.pow(opponent.len() as u32))
+            .map(|mut i| {
+                opponent
+                    .iter()
+                    .map(|_| {
+                        let ours = i % n;
+                        i /= n;
+                        ours
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|guide| meets(rules, opponent, guide, constraints))
+            .map(|guide| {
+                guide
+                    .iter()
+                    .zip(opponent)
+                    .map(|(&ours, &theirs)| rules.score(ours, theirs))
+                    .sum()
+            })
+            .max()
+    }
+
+    fn meets(
+        rules: &RuleSet,
+        opponent: &[usize],
+        guide: &[usize],
+        constraints: &Constraints,
+    ) -> bool {
+        let losses = guide
+            .iter()
+            .zip(opponent)
+            .filter(|&(&ours, &theirs)| rules.outcome(ours, theirs) == Outcome::Lose)
+            .count();
+        let within_limits = constraints.shape_limits.as_ref().is_none_or(|limits| {
+            limits
+                .iter()
+                .enumerate()
+                .all(|(s, &limit)| guide.iter().filter(|&&ours| ours == s).count() <= limit)
+        });
+        losses >= constraints.min_losses && within_limits
+    }
+
+    proptest! {
+        #[test]
+        fn matches_brute_force(
+            rpsls in any::<bool>(),
+            opponent in proptest::collection::vec(0..5_usize, 1..6),
+            limits in proptest::option::of(proptest::collection::vec(0..4_usize, 5)),
+            min_losses in 0..6_usize,
+        ) {
+            let rules = if rpsls { RuleSet::rpsls() } else { RuleSet::classic() };
+            let n = rules.shapes.len();
+            let opponent: Vec<usize> = opponent.into_iter().map(|t| t % n).collect();
+            let constraints = Constraints {
+                shape_limits: limits.map(|limits| limits[..n].to_vec()),
+                min_losses,
+            };
+            let best = optimise(&rules, &opponent, &constraints);
+            let expected = brute_force(&rules, &opponent, &constraints);
+            prop_assert_eq!(best.as_ref().map(|b| b.score), expected);
+            if let Some(best) = best {
+                prop_assert!(best.exact);
+                prop_assert!(meets(&rules, &opponent, &best.guide, &constraints));
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use color_eyre::eyre::{eyre, Report};
+
+const CLASSIC: &str = include_str!("../rules/classic.txt");
+const RPSLS: &str = include_str!("../rules/rpsls.txt");
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+
+    fn index(self) -> usize {
+        match self {
+            Outcome::Lose => 0,
+            Outcome::Draw => 1,
+            Outcome::Win => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Shape {
+    pub name: String,
+    pub score: u32,
+    pub opponent_letter: char,
+    pub our_letter: char,
+}
+
+// A game of n shapes where every pair of different shapes has exactly one winner
+#[derive(Clone, Debug)]
+pub struct RuleSet {
+    pub shapes: Vec<Shape>,
+    // beats[a][b] is true when shape a beats shape b
+    beats: Vec<Vec<bool>>,
+    outcome_scores: [u32; 3],
+    outcome_letters: [char; 3],
+}
+
+impl RuleSet {
+    pub fn classic() -> Self {
+        Self::parse(CLASSIC).expect("built-in classic rule set is valid")
+    }
+
+    pub fn rpsls() -> Self {
+        Self::parse(RPSLS).expect("built-in RPSLS rule set is valid")
+    }
+
+    // Parse a rule set config, see rules/classic.txt for the format
+    pub fn parse(config: &str) -> Result<Self, Report> {
+        let mut shapes: Vec<Shape> = vec![];
+        let mut beats_pairs = vec![];
+        let mut outcomes: [Option<(u32, char)>; 3] = [None; 3];
+
+        for (n, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let err = |msg: &str| eyre!("Line {}: {msg}: '{line}'", n + 1);
+            match fields.as_slice() {
+                ["shape", name, score, opponent, ours] => shapes.push(Shape {
+                    name: name.to_string(),
+                    score: score.parse().map_err(|_| err("invalid shape score"))?,
+                    opponent_letter: parse_letter(opponent).ok_or_else(|| err("invalid letter"))?,
+                    our_letter: parse_letter(ours).ok_or_else(|| err("invalid letter"))?,
+                }),
+                ["beats", winner, loser] => beats_pairs.push((n + 1, *winner, *loser)),
+                ["outcome", outcome, score, letter] => {
+                    let outcome = match *outcome {
+                        "lose" => Outcome::Lose,
+                        "draw" => Outcome::Draw,
+                        "win" => Outcome::Win,
+                        _ => return Err(err("unknown outcome")),
+                    };
+                    outcomes[outcome.index()] = Some((
+                        score.parse().map_err(|_| err("invalid outcome score"))?,
+                        parse_letter(letter).ok_or_else(|| err("invalid letter"))?,
+                    ));
+                }
+                _ => return Err(err("unrecognised rule")),
+            }
+        }
+
+        if shapes.is_empty() {
+            return Err(eyre!("Rule set has no shapes"));
+        }
+        for (i, shape) in shapes.iter().enumerate() {
+            for other in &shapes[..i] {
+                if other.name == shape.name {
+                    return Err(eyre!("Shape '{}' is defined twice", shape.name));
+                }
+                if other.opponent_letter == shape.opponent_letter
+                    || other.our_letter == shape.our_letter
+                {
+                    return Err(eyre!(
+                        "Shapes '{}' and '{}' share a letter",
+                        other.name,
+                        shape.name
+                    ));
+                }
+            }
+        }
+
+        let index_of = |name: &str| shapes.iter().position(|s| s.name == name);
+        let mut beats = vec![vec![false; shapes.len()]; shapes.len()];
+        for (line, winner, loser) in beats_pairs {
+            let (Some(w), Some(l)) = (index_of(winner), index_of(loser)) else {
+                return Err(eyre!(
+                    "Line {line}: unknown shape in '{winner} beats {loser}'"
+                ));
+            };
+            if w == l || beats[l][w] {
+                return Err(eyre!(
+                    "Line {line}: '{winner} beats {loser}' is contradictory"
+                ));
+            }
+            beats[w][l] = true;
+        }
+        for a in 0..shapes.len() {
+            for b in a + 1..shapes.len() {
+                if !beats[a][b] && !beats[b][a] {
+                    return Err(eyre!(
+                        "No winner defined between '{}' and '{}'",
+                        shapes[a].name,
+                        shapes[b].name
+                    ));
+                }
+            }
+        }
+
+        let mut outcome_scores = [0; 3];
+        let mut outcome_letters = [' '; 3];
+        for outcome in Outcome::ALL {
+            let (score, letter) = outcomes[outcome.index()]
+                .ok_or_else(|| eyre!("Missing score for outcome {outcome:?}"))?;
+            outcome_scores[outcome.index()] = score;
+            outcome_letters[outcome.index()] = letter;
+        }
+
+        Ok(RuleSet {
+            shapes,
+            beats,
+            outcome_scores,
+            outcome_letters,
+        })
+    }
+
+    // The outcome of a round, seen from our side
+    pub fn outcome(&self, ours: usize, theirs: usize) -> Outcome {
+        if ours == theirs {
+            Outcome::Draw
+        } else if self.beats[ours][theirs] {
+            Outcome::Win
+        } else {
+            Outcome::Lose
+        }
+    }
+
+    // The best scoring shape we can play against theirs to get the wanted outcome
+    pub fn shape_for(&self, theirs: usize, outcome: Outcome) -> Option<usize> {
+        (0..self.shapes.len())
+            .filter(|&ours| self.outcome(ours, theirs) == outcome)
+            .max_by_key(|&ours| self.shapes[ours].score)
+    }
+
+    pub fn shape_score(&self, shape: usize) -> u32 {
+        self.shapes[shape].score
+    }
+
+    pub fn outcome_score(&self, outcome: Outcome) -> u32 {
+        self.outcome_scores[outcome.index()]
+    }
+
+    pub fn score(&self, ours: usize, theirs: usize) -> u32 {
+        self.shape_score(ours) + self.outcome_score(self.outcome(ours, theirs))
+    }
+
+    pub fn opponent_shape(&self, letter: &str) -> Option<usize> {
+        let letter = parse_letter(letter)?;
+        self.shapes.iter().position(|s| s.opponent_letter == letter)
+    }
+
+    pub fn our_shape(&self, letter: &str) -> Option<usize> {
+        let letter = parse_letter(letter)?;
+        self.shapes.iter().position(|s| s.our_letter == letter)
+    }
+
+    pub fn wanted_outcome(&self, letter: &str) -> Option<Outcome> {
+        let letter = parse_letter(letter)?;
+        Outcome::ALL
+            .into_iter()
+            .find(|o| self.outcome_letters[o.index()] == letter)
+    }
+}
+
+fn parse_letter(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_rule_sets() {
+        let classic = RuleSet::classic();
+        assert_eq!(classic.shapes.len(), 3);
+        assert_eq!(classic.outcome(0, 2), Outcome::Win);
+        assert_eq!(classic.shape_for(1, Outcome::Lose), Some(0));
+
+        let rpsls = RuleSet::rpsls();
+        assert_eq!(rpsls.shapes.len(), 5);
+        // Spock vaporizes Rock, Lizard poisons Spock
+        assert_eq!(rpsls.outcome(4, 0), Outcome::Win);
+        assert_eq!(rpsls.outcome(4, 3), Outcome::Lose);
+        // Rock and Spock both beat Scissors, Spock scores more
+        assert_eq!(rpsls.shape_for(2, Outcome::Win), Some(4));
+    }
+
+    #[test]
+    fn invalid_rule_sets() {
+        assert!(RuleSet::parse("").is_err());
+        assert!(RuleSet::parse(&CLASSIC.replace("beats Paper Rock", "")).is_err());
+        let contradiction =
+            CLASSIC.replace("beats Paper Rock", "beats Paper Rock\nbeats Rock Paper");
+        assert!(RuleSet::parse(&contradiction).is_err());
+        assert!(RuleSet::parse(&CLASSIC.replace("B Y", "A Y")).is_err());
+        assert!(RuleSet::parse(&CLASSIC.replace("outcome win 6 Z", "")).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Write;
+
+use crate::rules::{Outcome, RuleSet};
+
+#[derive(Debug, PartialEq)]
+pub struct Round {
+    pub theirs: usize,
+    pub ours: usize,
+    pub outcome: Outcome,
+    pub shape_score: u32,
+    pub outcome_score: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub score: u32,
+}
+
+// Score every (theirs, ours) round, keeping a running total
+pub fn play(rules: &RuleSet, rounds: &[(usize, usize)]) -> Vec<Round> {
+    let mut total = 0;
+    rounds
+        .iter()
+        .map(|&(theirs, ours)| {
+            let outcome = rules.outcome(ours, theirs);
+            let shape_score = rules.shape_score(ours);
+            let outcome_score = rules.outcome_score(outcome);
+            total += shape_score + outcome_score;
+            Round {
+                theirs,
+                ours,
+                outcome,
+                shape_score,
+                outcome_score,
+                total,
+            }
+        })
+        .collect()
+}
+
+pub fn summarise(log: &[Round]) -> Summary {
+    let mut summary = Summary::default();
+    for round in log {
+        match round.outcome {
+            Outcome::Win => summary.wins += 1,
+            Outcome::Draw => summary.draws += 1,
+            Outcome::Lose => summary.losses += 1,
+        }
+        summary.score = round.total;
+    }
+    summary
+}
+
+pub fn to_text(rules: &RuleSet, part: &str, log: &[Round]) -> String {
+    let mut out = format!("{part}\n");
+    let width = rules.shapes.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    for (i, round) in log.iter().enumerate() {
+        writeln!(
+            out,
+            "Round {:>5}: {:<width$} vs {:<width$} {:<4} {} + {} = {:>2}, total {}",
+            i + 1,
+            rules.shapes[round.ours].name,
+            rules.shapes[round.theirs].name,
+            format!("{:?}", round.outcome),
+            round.shape_score,
+            round.outcome_score,
+            round.shape_score + round.outcome_score,
+            round.total,
+        )
+        .unwrap();
+    }
+    let summary = summarise(log);
+    writeln!(
+        out,
+        "Wins: {}, draws: {}, losses: {}, final score: {}",
+        summary.wins, summary.draws, summary.losses, summary.score
+    )
+    .unwrap();
+    out
+}
+
+// A CSV field, quoted when it holds a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Every round of each part with running tallies of the outcomes, then a
+// "total" round for the part adding up the scores
+pub fn to_csv(rules: &RuleSet, parts: &[(&str, &[Round])]) -> String {
+    let mut out = String::from(
+        "part,round,opponent,ours,outcome,shape_score,outcome_score,total,wins,draws,losses\n",
+    );
+    for (part, log) in parts {
+        let part = csv_field(part);
+        let mut tally = Summary::default();
+        for (i, round) in log.iter().enumerate() {
+            match round.outcome {
+                Outcome::Win => tally.wins += 1,
+                Outcome::Draw => tally.draws += 1,
+                Outcome::Lose => tally.losses += 1,
+            }
+            writeln!(
+                out,
+                "{part},{},{},{},{:?},{},{},{},{},{},{}",
+                i + 1,
+                csv_field(&rules.shapes[round.theirs].name),
+                csv_field(&rules.shapes[round.ours].name),
+                round.outcome,
+                round.shape_score,
+                round.outcome_score,
+                round.total,
+                tally.wins,
+                tally.draws,
+                tally.losses,
+            )
+            .unwrap();
+        }
+        let summary = summarise(log);
+        writeln!(
+            out,
+            "{part},total,,,,{},{},{},{},{},{}",
+            log.iter().map(|r| r.shape_score).sum::<u32>(),
+            log.iter().map(|r| r.outcome_score).sum::<u32>(),
+            summary.score,
+            summary.wins,
+            summary.draws,
+            summary.losses,
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_and_summary() {
+        let rules = RuleSet::classic();
+        // The example guide: Paper vs Rock, Rock vs Paper, Scissors vs Scissors
+        let log = play(&rules, &[(0, 1), (1, 0), (2, 2)]);
+        assert_eq!(
+            log.iter().map(|r| r.total).collect::<Vec<_>>(),
+            vec![8, 9, 15]
+        );
+        assert_eq!(
+            summarise(&log),
+            Summary {
+                wins: 1,
+                draws: 1,
+                losses: 1,
+                score: 15
+            }
+        );
+        let csv = to_csv(&rules, &[("1", &log)]);
+        assert_eq!(csv.lines().nth(1), Some("1,1,Rock,Paper,Win,2,6,8,1,0,0"));
+        assert_eq!(csv.lines().last(), Some("1,total,,,,6,9,15,1,1,1"));
+    }
+
+    #[test]
+    fn quoted_csv_names() {
+        let mut rules = RuleSet::classic();
+        rules.shapes[0].name = "Rock, big".to_string();
+        rules.shapes[1].name = "\"Paper\"".to_string();
+        let log = play(&rules, &[(0, 1)]);
+        assert_eq!(
+            to_csv(&rules, &[("1", &log)]).lines().nth(1),
+            Some("1,1,\"Rock, big\",\"\"\"Paper\"\"\",Win,2,6,8,1,0,0")
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[path = "../src/rucksack.rs"]
+#[allow(dead_code)]
+mod rucksack;
+
+use rucksack::{common_items, priority, Rucksack};
+
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// The nested loop implementation the bitset replaced
+mod naive {
+    pub fn part1(input: &str) -> i32 {
+        let mut score: i32 = 0;
+        for line in input.lines() {
+            let (beginning, end) = line.split_at(line.len() / 2);
+            for c in beginning.chars() {
+                if end.contains(c) {
+                    score += get_priority(c);
+                    break;
+                }
+            }
+        }
+        score
+    }
+
+    pub fn part2(input: &str) -> i32 {
+        let mut score: i32 = 0;
+        let lines: Vec<&str> = input.lines().collect();
+        for chunk in lines.chunks(3) {
+            if let [first, second, third] = chunk {
+                for c in first.chars() {
+                    if second.contains(c) && third.contains(c) {
+                        score += get_priority(c);
+                        break;
+                    }
+                }
+            }
+        }
+        score
+    }
+
+    fn get_priority(c: char) -> i32 {
+        if c.is_lowercase() {
+            c as i32 - 96
+        } else {
+            c as i32 - 65 + 27
+        }
+    }
+}
+
+fn part1(input: &str) -> u32 {
+    input
+        .lines()
+        .map(|line| priority(Rucksack::parse(line).unwrap().misplaced()))
+        .sum()
+}
+
+fn part2(input: &str) -> u32 {
+    let rucksacks: Vec<Rucksack> = input
+        .lines()
+        .map(|line| Rucksack::parse(line).unwrap())
+        .collect();
+    rucksacks
+        .chunks_exact(3)
+        .map(|group| priority(common_items(group)))
+        .sum()
+}
+
+// xorshift, good enough to scatter items
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle(&mut self, items: &mut [u8]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// Rucksacks with exactly one item type in both compartments, and groups of
+// three sharing exactly one badge, like the puzzle input
+fn generate(groups: usize, compartment: usize) -> String {
+    let mut rng = Rng(0x2022_1203);
+    let mut out = String::new();
+    for _ in 0..groups {
+        let mut items = ITEMS.to_vec();
+        rng.shuffle(&mut items);
+        // One badge, then 17 items for each elf that the other two don't carry
+        let (badge, pools) = (items[0], &items[1..]);
+        for pool in pools.chunks(17) {
+            let (misplaced, first, second) = (pool[0], &pool[1..9], &pool[9..]);
+            let mut left = vec![badge, misplaced];
+            left.extend((2..compartment).map(|_| first[rng.below(first.len())]));
+            let mut right = vec![misplaced];
+            right.extend((1..compartment).map(|_| second[rng.below(second.len())]));
+            for half in [&mut left, &mut right] {
+                rng.shuffle(half);
+                out.push_str(std::str::from_utf8(half).unwrap());
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn bench(c: &mut Criterion) {
+    for compartment in [16, 256, 4096] {
+        let input = generate(100, compartment);
+        assert_eq!(part1(&input) as i32, naive::part1(&input));
+        assert_eq!(part2(&input) as i32, naive::part2(&input));
+
+        let mut group = c.benchmark_group(format!("compartment {compartment}"));
+        group.bench_with_input(BenchmarkId::new("naive", "part1"), &input, |b, i| {
+            b.iter(|| naive::part1(black_box(i)))
+        });
+        group.bench_with_input(BenchmarkId::new("bitset", "part1"), &input, |b, i| {
+            b.iter(|| part1(black_box(i)))
+        });
+        group.bench_with_input(BenchmarkId::new("naive", "part2"), &input, |b, i| {
+            b.iter(|| naive::part2(black_box(i)))
+        });
+        group.bench_with_input(BenchmarkId::new("bitset", "part2"), &input, |b, i| {
+            b.iter(|| part2(black_box(i)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
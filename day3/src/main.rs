@@ -1,49 +1,151 @@
-fn main() {
-    let input: &str = include_str!("../input");
-    println!("Part 1: {}", part1(input));
-    println!("Part 2: {}", part2(input));
-}
+mod planner;
+mod rucksack;
+
+use color_eyre::eyre::{eyre, Report};
+use rucksack::{common_items, list_items, priority, Rucksack};
+use std::fmt::Write;
 
-fn part1(input: &str) -> i32 {
-    // Split each string in two halves, find the common char and assign priority
-    let mut score: i32 = 0;
-    for line in input.lines() {
-        let (beginning, end) = line.split_at(line.len() / 2);
-        for c in beginning.chars() {
-            if end.contains(c) {
-                score += get_priority(c);
-                break;
+// Usage: day3 [--group-size n] [--badges] [--plan] [input file]
+fn main() -> Result<(), Report> {
+    color_eyre::install()?;
+    let mut input = include_str!("../input").to_string();
+    let mut group_size = 3;
+    let mut badges = false;
+    let mut plan = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--group-size" => {
+                group_size = args
+                    .next()
+                    .ok_or_else(|| eyre!("--group-size needs a number"))?
+                    .parse()?;
+                if group_size == 0 {
+                    return Err(eyre!("Groups need at least one rucksack"));
+                }
             }
+            "--badges" => badges = true,
+            "--plan" => plan = true,
+            path => input = std::fs::read_to_string(path)?,
         }
     }
-    score
+
+    if plan {
+        print!("{}", reorganisation_plan(&input)?);
+        return Ok(());
+    }
+    if badges {
+        print!("{}", badge_report(&input, group_size)?);
+        return Ok(());
+    }
+    println!("Part 1: {}", part1(&input)?);
+    println!("Part 2: {}", part2(&input, group_size)?);
+    Ok(())
 }
 
-fn part2(input: &str) -> i32 {
-    // Iterate three lines at a time, find the common char and assign priority
-    let mut score: i32 = 0;
-    let lines: Vec<&str> = input.lines().collect();
-    for chunk in lines.chunks(3) {
-        if let [first, second, third] = chunk {
-            for c in first.chars() {
-                if second.contains(c) && third.contains(c) {
-                    score += get_priority(c);
-                    break;
-                }
+fn parse(input: &str) -> Result<Vec<Rucksack>, Report> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(n, line)| Rucksack::parse(line).map_err(|e| eyre!("Line {}: {e}: '{line}'", n + 1)))
+        .collect()
+}
+
+fn part1(input: &str) -> Result<u32, Report> {
+    // Split each string in two halves, find the common item and assign priority
+    Ok(parse(input)?
+        .into_iter()
+        .map(|rucksack| priority(rucksack.misplaced()))
+        .sum())
+}
+
+// Split into groups of group_size rucksacks, refusing a smaller group at the end
+fn groups(rucksacks: &[Rucksack], group_size: usize) -> Result<Vec<&[Rucksack]>, Report> {
+    let groups = rucksacks.chunks_exact(group_size);
+    if !groups.remainder().is_empty() {
+        return Err(eyre!(
+            "Line {}: incomplete group of {} rucksack(s) at the end, groups have {group_size}",
+            rucksacks.len() - groups.remainder().len() + 1,
+            groups.remainder().len()
+        ));
+    }
+    Ok(groups.collect())
+}
+
+fn lines(group: usize, group_size: usize) -> String {
+    format!(
+        "lines {}-{}",
+        group * group_size + 1,
+        (group + 1) * group_size
+    )
+}
+
+fn part2(input: &str, group_size: usize) -> Result<u32, Report> {
+    // Iterate group_size lines at a time, find the one common item and assign priority
+    let rucksacks = parse(input)?;
+    let mut score = 0;
+    for (i, group) in groups(&rucksacks, group_size)?.into_iter().enumerate() {
+        let common = common_items(group);
+        if common.count_ones() != 1 {
+            return Err(eyre!(
+                "Group {} ({}) has {} common items, expected one badge",
+                i + 1,
+                lines(i, group_size),
+                common.count_ones()
+            ));
+        }
+        score += priority(common);
+    }
+    Ok(score)
+}
+
+// List the badge of every group, flagging groups without exactly one common item
+fn badge_report(input: &str, group_size: usize) -> Result<String, Report> {
+    let rucksacks = parse(input)?;
+    let mut report = String::new();
+    for (i, group) in groups(&rucksacks, group_size)?.into_iter().enumerate() {
+        let common: Vec<_> = list_items(common_items(group)).collect();
+        write!(report, "Group {} ({}): ", i + 1, lines(i, group_size))?;
+        match common.as_slice() {
+            [] => writeln!(report, "NO common item")?,
+            [(item, priority)] => writeln!(report, "badge '{item}', priority {priority}")?,
+            items => {
+                let items: Vec<_> = items
+                    .iter()
+                    .map(|(item, priority)| format!("'{item}' ({priority})"))
+                    .collect();
+                writeln!(report, "{} common items: {}", items.len(), items.join(", "))?
             }
         }
     }
-    score
+    Ok(report)
 }
 
-fn get_priority(c: char) -> i32 {
-    // Translate to badge priority
-    // a-z = 1-26, A-Z = 27-52
-    if c.is_lowercase() {
-        c as i32 - 96 // ASCII a == 97
-    } else {
-        c as i32 - 65 + 27 // ASCII A == 65
+// The fewest item moves per rucksack so no item type is in both compartments
+fn reorganisation_plan(input: &str) -> Result<String, Report> {
+    parse(input)?;
+    let mut report = String::new();
+    let mut total = 0;
+    for (n, line) in input.lines().enumerate() {
+        write!(report, "Line {}: ", n + 1)?;
+        let Some(moves) = planner::plan(line) else {
+            writeln!(report, "impossible with equal compartments")?;
+            continue;
+        };
+        let moves_made = planner::total_moves(&moves);
+        total += moves_made;
+        let moves: Vec<_> = moves
+            .iter()
+            .map(|m| {
+                let to = if m.to_second { "second" } else { "first" };
+                format!("{} '{}' to {to}", m.count, m.item)
+            })
+            .collect();
+        writeln!(report, "{moves_made} moves: {}", moves.join(", "))?;
     }
+    writeln!(report, "Total moves: {total}")?;
+    Ok(report)
 }
 
 #[cfg(test)]
@@ -55,13 +157,55 @@ wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw";
 
+    const INPUT: &str = include_str!("../input");
+
     #[test]
     fn part1() {
-        assert_eq!(super::part1(TEST_INPUT), 157)
+        assert_eq!(super::part1(TEST_INPUT).unwrap(), 157);
+        assert_eq!(super::part1(INPUT).unwrap(), 7811);
     }
 
     #[test]
     fn part2() {
-        assert_eq!(super::part2(TEST_INPUT), 70);
+        assert_eq!(super::part2(TEST_INPUT, 3).unwrap(), 70);
+        assert_eq!(super::part2(INPUT, 3).unwrap(), 2639);
+        // Pairs of rucksacks share several items
+        assert!(super::part2(TEST_INPUT, 2).is_err());
+        // Each rucksack on its own has every item in common
+        assert!(super::part2("ab", 1).is_err());
+        assert_eq!(super::part2("aa\nab\naB", 3).unwrap(), 1);
+    }
+
+    #[test]
+    fn badge_report() {
+        assert_eq!(
+            super::badge_report(TEST_INPUT, 3).unwrap(),
+            "Group 1 (lines 1-3): badge 'r', priority 18
+Group 2 (lines 4-6): badge 'Z', priority 52
+"
+        );
+        assert_eq!(
+            super::badge_report("abcd\nbcda\nAbCD\nefgh", 2).unwrap(),
+            "Group 1 (lines 1-2): 4 common items: 'a' (1), 'b' (2), 'c' (3), 'd' (4)
+Group 2 (lines 3-4): NO common item
+"
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        let error = |input| super::part1(input).unwrap_err().to_string();
+        assert_eq!(
+            error("abab\nabcab"),
+            "Line 2: Odd number of items (5) can't be split into two compartments: 'abcab'"
+        );
+        assert_eq!(error("ab1b"), "Line 1: Invalid item '1': 'ab1b'");
+        assert_eq!(error("aåbc"), "Line 1: Invalid item 'å': 'aåbc'");
+        assert_eq!(
+            super::part2(TEST_INPUT.rsplit_once('\n').unwrap().0, 3)
+                .unwrap_err()
+                .to_string(),
+            "Line 4: incomplete group of 2 rucksack(s) at the end, groups have 3"
+        );
     }
 }
@@ -0,0 +1,114 @@
+use crate::rucksack::{item_priority, list_items};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Move {
+    pub item: char,
+    pub count: usize,
+    // From the first compartment to the second, or the other way around
+    pub to_second: bool,
+}
+
+// Items moved between compartments so that every item type is in only one of
+// them while both keep the same number of items. None if no such split exists.
+pub fn plan(line: &str) -> Option<Vec<Move>> {
+    let (first, second) = line.split_at(line.len() / 2);
+    let (first, second) = (counts(first), counts(second));
+    let types: Vec<(char, u32)> = list_items(u64::MAX).collect();
+    let size = line.len() / 2;
+
+    // best[t][w]: most items left in place using the first t types when
+    // w items end up in the first compartment
+    let mut best = vec![vec![None; size + 1]; types.len() + 1];
+    best[0][0] = Some(0);
+    for (t, &(_, p)) in types.iter().enumerate() {
+        let (a, b) = (first[p as usize], second[p as usize]);
+        for w in 0..=size {
+            let Some(kept) = best[t][w] else { continue };
+            // Everything of this type goes to the second compartment
+            relax(&mut best[t + 1][w], kept + b);
+            // Everything of this type goes to the first compartment
+            if w + a + b <= size {
+                relax(&mut best[t + 1][w + a + b], kept + a);
+            }
+        }
+    }
+    best[types.len()][size]?;
+
+    // Walk back through the table to find where each type went
+    let mut moves = vec![];
+    let mut w = size;
+    for (t, &(item, p)) in types.iter().enumerate().rev() {
+        let (a, b) = (first[p as usize], second[p as usize]);
+        let kept = best[t + 1][w].unwrap();
+        if kept >= b && best[t][w] == Some(kept - b) {
+            if a > 0 {
+                moves.push(Move {
+                    item,
+                    count: a,
+                    to_second: true,
+                });
+            }
+        } else {
+            w -= a + b;
+            if b > 0 {
+                moves.push(Move {
+                    item,
+                    count: b,
+                    to_second: false,
+                });
+            }
+        }
+    }
+    moves.reverse();
+    Some(moves)
+}
+
+fn relax(slot: &mut Option<usize>, kept: usize) {
+    if slot.is_none_or(|best| kept > best) {
+        *slot = Some(kept);
+    }
+}
+
+fn counts(items: &str) -> [usize; 53] {
+    let mut counts = [0; 53];
+    for item in items.bytes() {
+        counts[item_priority(item) as usize] += 1;
+    }
+    counts
+}
+
+pub fn total_moves(moves: &[Move]) -> usize {
+    moves.iter().map(|m| m.count).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans() {
+        // Move one p across and something else back to keep the sizes equal
+        assert_eq!(total_moves(&plan("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap()), 2);
+
+        // Already sorted
+        assert_eq!(plan("abcd").unwrap(), vec![]);
+        // Both halves are full of a, which can't be split
+        assert_eq!(plan("aaaa"), None);
+        // Keep the b's in the first half: swap the a there for the b in the second
+        assert_eq!(
+            plan("abbcab").unwrap(),
+            vec![
+                Move {
+                    item: 'a',
+                    count: 1,
+                    to_second: true
+                },
+                Move {
+                    item: 'b',
+                    count: 1,
+                    to_second: false
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use color_eyre::eyre::{eyre, Report};
+
+// Items are stored as a bitset where bit n is set for an item of priority n
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rucksack {
+    pub first: u64,
+    pub second: u64,
+}
+
+impl Rucksack {
+    pub fn parse(line: &str) -> Result<Self, Report> {
+        if let Some(item) = line.chars().find(|c| !c.is_ascii_alphabetic()) {
+            return Err(eyre!("Invalid item '{item}'"));
+        }
+        if !line.len().is_multiple_of(2) {
+            return Err(eyre!(
+                "Odd number of items ({}) can't be split into two compartments",
+                line.len()
+            ));
+        }
+        let (first, second) = line.split_at(line.len() / 2);
+        Ok(Rucksack {
+            first: item_mask(first),
+            second: item_mask(second),
+        })
+    }
+
+    pub fn items(self) -> u64 {
+        self.first | self.second
+    }
+
+    // Items found in both compartments
+    pub fn misplaced(self) -> u64 {
+        self.first & self.second
+    }
+}
+
+// Items found in every rucksack of the group
+pub fn common_items(group: &[Rucksack]) -> u64 {
+    group.iter().fold(u64::MAX, |common, r| common & r.items())
+}
+
+// Priority of the lowest item in the mask, 0 for an empty mask
+pub fn priority(mask: u64) -> u32 {
+    if mask == 0 {
+        0
+    } else {
+        mask.trailing_zeros()
+    }
+}
+
+// Every item in the mask with its priority, lowest priority first
+pub fn list_items(mask: u64) -> impl Iterator<Item = (char, u32)> {
+    (1..=52)
+        .filter(move |p| mask & 1 << p != 0)
+        .map(|p| (ITEMS[p as usize - 1] as char, p))
+}
+
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+// Items must be ASCII letters, anything else is ignored
+pub fn item_mask(items: &str) -> u64 {
+    items
+        .bytes()
+        .fold(0, |mask, b| mask | 1 << PRIORITIES[b as usize])
+        & !1
+}
+
+pub fn item_priority(item: u8) -> u32 {
+    PRIORITIES[item as usize] as u32
+}
+
+// Translate to badge priority
+// a-z = 1-26, A-Z = 27-52
+const PRIORITIES: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 26 {
+        table[b'a' as usize + i] = i as u8 + 1;
+        table[b'A' as usize + i] = i as u8 + 27;
+        i += 1;
+    }
+    table
+};
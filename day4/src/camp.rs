@@ -0,0 +1,113 @@
+use crate::interval::Interval;
+use crate::Sections;
+
+// How many elves clean each stretch of the camp, from the lowest assigned
+// section to the highest. Built with a sweep over the assignment ends.
+pub struct Coverage {
+    segments: Vec<(Sections, usize)>,
+    // Segments cleaned by at most one elf, in order, for covered_by_others
+    thin: Vec<Sections>,
+}
+
+impl Coverage {
+    pub fn new(assignments: &[Sections]) -> Self {
+        // An assignment adds an elf at its start and removes it after its end
+        let mut events: Vec<(u64, isize)> = assignments
+            .iter()
+            .flat_map(|a| [(a.start() as u64, 1), (a.end() as u64 + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut segments = vec![];
+        let mut elves = 0;
+        for (i, &(position, change)) in events.iter().enumerate() {
+            elves += change;
+            let Some(&(next, _)) = events.get(i + 1) else {
+                break;
+            };
+            if next > position {
+                let segment = Interval::new(position as u32, (next - 1) as u32).unwrap();
+                segments.push((segment, elves as usize));
+            }
+        }
+
+        let thin = segments
+            .iter()
+            .filter(|&&(_, elves)| elves <= 1)
+            .map(|&(segment, _)| segment)
+            .collect();
+        Coverage { segments, thin }
+    }
+
+    // Sections between the first and last assigned ones that nobody cleans
+    pub fn uncovered(&self) -> Vec<Sections> {
+        self.matching(|elves| elves == 0)
+    }
+
+    pub fn covered_more_than(&self, k: usize) -> Vec<Sections> {
+        self.matching(|elves| elves > k)
+    }
+
+    // Every section of the assignment is also cleaned by some other elf,
+    // meaning none of it falls in a segment with one elf or less
+    pub fn covered_by_others(&self, assignment: &Sections) -> bool {
+        let first_after = self
+            .thin
+            .partition_point(|segment| segment.end() < assignment.start());
+        self.thin
+            .get(first_after)
+            .is_none_or(|segment| !segment.overlaps(assignment))
+    }
+
+    // Merged stretches of segments whose number of elves passes the test
+    fn matching(&self, test: impl Fn(usize) -> bool) -> Vec<Sections> {
+        let mut stretches: Vec<Sections> = vec![];
+        for &(segment, elves) in &self.segments {
+            if !test(elves) {
+                continue;
+            }
+            match stretches.last_mut() {
+                Some(last) if last.end() as u64 + 1 == segment.start() as u64 => {
+                    *last = last.union(&segment)[0];
+                }
+                _ => stretches.push(segment),
+            }
+        }
+        stretches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camp_coverage() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        // ..345......
+        // .23........
+        // ....56.....
+        // .......89..
+        // ........9ab
+        let assignments = [s(3, 5), s(2, 3), s(5, 6), s(8, 9), s(9, 11)];
+        let coverage = Coverage::new(&assignments);
+
+        assert_eq!(coverage.uncovered(), vec![s(7, 7)]);
+        assert_eq!(
+            coverage.covered_more_than(1),
+            vec![s(3, 3), s(5, 5), s(9, 9)]
+        );
+        assert_eq!(coverage.covered_more_than(0), vec![s(2, 6), s(8, 11)]);
+        assert_eq!(
+            assignments.map(|a| coverage.covered_by_others(&a)),
+            [false, false, false, false, false]
+        );
+
+        let coverage = Coverage::new(&[s(1, 4), s(3, 8), s(2, 5), s(6, 6)]);
+        assert!(coverage.covered_by_others(&s(2, 5)));
+        assert!(coverage.covered_by_others(&s(6, 6)));
+        assert!(!coverage.covered_by_others(&s(1, 4)));
+        assert!(!coverage.covered_by_others(&s(3, 8)));
+        assert_eq!(coverage.uncovered(), vec![]);
+    }
+}
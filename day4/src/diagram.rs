@@ -0,0 +1,127 @@
+use crate::camp::Coverage;
+use crate::interval::Interval;
+use crate::Sections;
+
+// Which sections the columns of a diagram show
+#[derive(Debug, PartialEq)]
+pub struct View {
+    // Wider than the section IDs, so that the last column can go past them
+    first: u64,
+    per_column: u64,
+    columns: u64,
+}
+
+impl View {
+    // Start at section 1 like the puzzle description, or 0 if that is used,
+    // when that fits, and put several sections in each column when the
+    // diagram gets too wide
+    pub fn fit<'a>(assignments: impl Iterator<Item = &'a Sections>, max_width: u32) -> View {
+        let (mut lowest, mut highest) = (u64::from(u32::MAX), 1);
+        for a in assignments {
+            lowest = lowest.min(u64::from(a.start()));
+            highest = highest.max(u64::from(a.end()));
+        }
+        let max_width = u64::from(max_width);
+        let first = match lowest.min(1) {
+            start if highest - start < max_width => start,
+            _ => lowest,
+        };
+        let span = highest - first + 1;
+        let per_column = span.div_ceil(max_width);
+        View {
+            first,
+            per_column,
+            columns: span.div_ceil(per_column),
+        }
+    }
+
+    fn column(&self, column: u64) -> Sections {
+        let start = self.first + column * self.per_column;
+        let end = (start + self.per_column - 1).min(u64::from(u32::MAX));
+        Interval::new(start as u32, end as u32).unwrap()
+    }
+
+    // The section's last digit or '.', or when a column holds several
+    // sections '#' if the assignment has all of them, '+' for some, '.' for none
+    fn cell(&self, column: Sections, assignment: &Sections) -> char {
+        match column.intersection(assignment).map_or(0, |i| i.len()) {
+            0 => '.',
+            _ if self.per_column == 1 => char::from_digit(column.start() % 10, 10).unwrap(),
+            n if n == column.len() => '#',
+            _ => '+',
+        }
+    }
+
+    // A line per assignment as in the puzzle description, followed by a line
+    // marking with '^' the sections more than one elf cleans
+    pub fn render(&self, group: &[Sections]) -> String {
+        let mut out = String::new();
+        for assignment in group {
+            let cells: String = (0..self.columns)
+                .map(|c| self.cell(self.column(c), assignment))
+                .collect();
+            out += &format!("{cells} {assignment}\n");
+        }
+
+        let overlap = Coverage::new(group).covered_more_than(1);
+        if !overlap.is_empty() {
+            let marks: String = (0..self.columns)
+                .map(|c| {
+                    if overlap.iter().any(|o| o.overlaps(&self.column(c))) {
+                        '^'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            out += &format!("{marks}\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzle_diagrams() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        let groups = [vec![s(2, 4), s(6, 8)], vec![s(5, 7), s(7, 9)]];
+        let view = View::fit(groups.iter().flatten(), 80);
+        assert_eq!(view.render(&groups[0]), ".234..... 2-4\n.....678. 6-8\n");
+        assert_eq!(
+            view.render(&groups[1]),
+            "....567.. 5-7\n......789 7-9\n......^..\n"
+        );
+    }
+
+    #[test]
+    fn scaled_diagrams() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        let group = [s(100, 139), s(125, 199)];
+        // 100 sections from 100, 10 in each column
+        let view = View::fit(group.iter(), 10);
+        assert_eq!(
+            view.render(&group),
+            "####...... 100-139\n..+####### 125-199\n..^^......\n"
+        );
+    }
+
+    #[test]
+    fn section_zero() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        let group = [s(0, 3), s(2, 5)];
+        let view = View::fit(group.iter(), 80);
+        assert_eq!(view.render(&group), "0123.. 0-3\n..2345 2-5\n..^^..\n");
+    }
+
+    #[test]
+    fn whole_range() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        let group = [s(0, u32::MAX), s(1, 2)];
+        let view = View::fit(group.iter(), 4);
+        assert_eq!(view.columns, 4);
+        assert_eq!(view.render(&group), "#### 0-4294967295\n+... 1-2\n^...\n");
+    }
+}
@@ -0,0 +1,166 @@
+// General purpose, the puzzle itself only needs part of it
+#![allow(dead_code)]
+
+use std::fmt;
+
+// Integer types an interval can be made of
+pub trait Integer: Copy + Ord + fmt::Debug + fmt::Display {
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    // Number of integers from self up to and including end
+    fn count_to(self, end: Self) -> u128;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(impl Integer for $t {
+            fn succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn count_to(self, end: Self) -> u128 {
+                (end as i128 - self as i128) as u128 + 1
+            }
+        })*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// A non-empty, inclusive range of integers
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    start: T,
+    end: T,
+}
+
+impl<T: Integer> Interval<T> {
+    // None if end is before start
+    pub fn new(start: T, end: T) -> Option<Self> {
+        (start <= end).then_some(Interval { start, end })
+    }
+
+    pub fn start(&self) -> T {
+        self.start
+    }
+
+    pub fn end(&self) -> T {
+        self.end
+    }
+
+    pub fn len(&self) -> u128 {
+        self.start.count_to(self.end)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        other.start <= self.start && self.end <= other.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        Interval::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // One interval if they overlap or touch, otherwise both in order
+    pub fn union(&self, other: &Self) -> Vec<Self> {
+        let (first, last) = if self <= other {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        if first.end.succ().is_none_or(|next| last.start <= next) {
+            vec![Interval {
+                start: first.start,
+                end: first.end.max(last.end),
+            }]
+        } else {
+            vec![*first, *last]
+        }
+    }
+
+    // The parts of self not in other: none, one, or two intervals in order
+    pub fn difference(&self, other: &Self) -> Vec<Self> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+        let before = other
+            .start
+            .pred()
+            .and_then(|end| Interval::new(self.start, end));
+        let after = other
+            .end
+            .succ()
+            .and_then(|start| Interval::new(start, self.end));
+        before.into_iter().chain(after).collect()
+    }
+}
+
+impl<T: Integer> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn interval() -> impl Strategy<Value = Interval<u8>> {
+        (any::<u8>(), any::<u8>()).prop_map(|(a, b)| Interval::new(a.min(b), a.max(b)).unwrap())
+    }
+
+    fn set(intervals: &[Interval<u8>]) -> BTreeSet<u8> {
+        intervals.iter().flat_map(|i| i.start..=i.end).collect()
+    }
+
+    // Intervals in a result are in order and neither overlap nor touch
+    fn disjoint(intervals: &[Interval<u8>]) -> bool {
+        intervals
+            .windows(2)
+            .all(|w| w[0].end.succ().is_some_and(|next| next < w[1].start))
+    }
+
+    proptest! {
+        #[test]
+        fn matches_set_operations(a in interval(), b in interval()) {
+            let (sa, sb) = (set(&[a]), set(&[b]));
+            prop_assert_eq!(a.len(), sa.len() as u128);
+            prop_assert_eq!(a.is_subset(&b), sa.is_subset(&sb));
+            prop_assert_eq!(a.overlaps(&b), !sa.is_disjoint(&sb));
+
+            let intersection = a.intersection(&b);
+            prop_assert_eq!(set(intersection.as_slice()), &sa & &sb);
+
+            let union = a.union(&b);
+            prop_assert!(!union.is_empty() && union.len() <= 2 && disjoint(&union));
+            prop_assert_eq!(set(&union), &sa | &sb);
+
+            let difference = a.difference(&b);
+            prop_assert!(difference.len() <= 2 && disjoint(&difference));
+            prop_assert_eq!(set(&difference), &sa - &sb);
+        }
+    }
+
+    #[test]
+    fn edges_of_the_type() {
+        let all = Interval::new(u8::MIN, u8::MAX).unwrap();
+        assert_eq!(all.len(), 256);
+        assert_eq!(all.difference(&all), vec![]);
+        let signed = Interval::new(i64::MIN, i64::MAX).unwrap();
+        assert_eq!(signed.len(), 1 << 64);
+        assert_eq!(Interval::new(3, 2), None);
+    }
+}
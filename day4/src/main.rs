@@ -1,72 +1,196 @@
+mod camp;
+mod diagram;
+mod interval;
+mod report;
+
 use color_eyre::eyre::{eyre, Report};
+use interval::Interval;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-#[derive(Copy, Clone, Debug)]
-struct Range {
-    start: u8,
-    end: u8,
-}
+type Sections = Interval<u32>;
 
-impl Range {
-    fn contains(self, value: &u8) -> bool {
-        let range = self.start..=self.end;
-        range.contains(value)
+// Parse "start-end" with both ends within range and start <= end
+fn parse_assignment(assignment: &str) -> Result<Sections, Report> {
+    lazy_static! {
+        static ref RE: Regex = Regex::new(r"^(?P<start>\d+)-(?P<end>\d+)$").unwrap();
     }
+    let groups = RE
+        .captures(assignment)
+        .ok_or_else(|| eyre!("'{assignment}' is not a 'start-end' assignment"))?;
+    let section = |name: &str| {
+        groups[name].parse::<u32>().map_err(|_| {
+            eyre!(
+                "section ID '{}' in '{assignment}' is too large",
+                &groups[name]
+            )
+        })
+    };
+    Interval::new(section("start")?, section("end")?)
+        .ok_or_else(|| eyre!("assignment '{assignment}' ends before it starts"))
 }
 
-fn find_parts(line: &str) -> Result<(Range, Range), Report> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(
-            r"(?P<first_start>\d+)-(?P<first_end>\d+),(?P<last_start>\d+)-(?P<last_end>\d+)"
-        )
-        .unwrap();
+// Parse "first-last" line numbers, counting from 1
+fn parse_lines(lines: &str) -> Result<Sections, Report> {
+    let range = lines
+        .split_once('-')
+        .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)));
+    match range {
+        Some((first, last)) if first >= 1 => Interval::new(first, last)
+            .ok_or_else(|| eyre!("line range '{lines}' ends before it starts")),
+        _ => Err(eyre!("'{lines}' is not a 'first-last' line range")),
     }
-    if let Some(groups) = RE.captures(line) {
-        Ok((
-            Range {
-                start: groups["first_start"].parse::<u8>()?,
-                end: groups["first_end"].parse::<u8>()?,
-            },
-            Range {
-                start: groups["last_start"].parse::<u8>()?,
-                end: groups["last_end"].parse::<u8>()?,
-            },
-        ))
-    } else {
-        Err(eyre!("Failed to find two groups of two u8 in line.",))
+}
+
+// A group of two or more comma separated assignments
+fn find_parts(line: &str) -> Result<Vec<Sections>, Report> {
+    let parts: Vec<&str> = line.split(',').collect();
+    if parts.len() < 2 {
+        return Err(eyre!("expected at least two assignments in '{line}'"));
     }
+    parts.into_iter().map(parse_assignment).collect()
+}
+
+fn parse(input: &str) -> Result<Vec<Vec<Sections>>, Report> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(n, line)| find_parts(line).map_err(|e| eyre!("Line {}: {e}", n + 1)))
+        .collect()
+}
+
+// Every way to pick two different assignments from the group
+fn pairs(group: &[Sections]) -> impl Iterator<Item = (&Sections, &Sections)> {
+    group
+        .iter()
+        .enumerate()
+        .flat_map(move |(i, a)| group[i + 1..].iter().map(move |b| (a, b)))
 }
 
 fn score(input: &str) -> Result<(u32, u32), Report> {
     let mut p1_score = 0;
     let mut p2_score = 0;
-    for line in input.lines() {
-        let (first, last) = find_parts(line)?;
-
+    for group in parse(input)? {
         // Part 1
-        if (first.contains(&last.start) && first.contains(&last.end))
-            || (last.contains(&first.start) && last.contains(&first.end))
-        {
+        if pairs(&group).any(|(a, b)| a.is_subset(b) || b.is_subset(a)) {
             p1_score += 1
         }
 
         // Part 2
-        if first.contains(&last.start)
-            || first.contains(&last.end)
-            || last.contains(&first.start)
-            || last.contains(&first.end)
-        {
+        if pairs(&group).any(|(a, b)| a.overlaps(b)) {
             p2_score += 1
         }
     }
     Ok((p1_score, p2_score))
 }
 
+// Camp wide: sections nobody cleans, sections more than k elves clean, and
+// elves whose whole assignment is cleaned by others anyway
+fn camp_report(input: &str, k: usize) -> Result<String, Report> {
+    let groups = parse(input)?;
+    let elves: Vec<(usize, usize)> = groups
+        .iter()
+        .enumerate()
+        .flat_map(|(line, group)| (0..group.len()).map(move |elf| (line, elf)))
+        .collect();
+    let assignments: Vec<Sections> = groups.into_iter().flatten().collect();
+    let coverage = camp::Coverage::new(&assignments);
+    let list = |sections: Vec<Sections>| {
+        let sections: Vec<_> = sections.iter().map(|s| s.to_string()).collect();
+        if sections.is_empty() {
+            "none".to_string()
+        } else {
+            sections.join(", ")
+        }
+    };
+
+    let mut out = format!("Not covered: {}\n", list(coverage.uncovered()));
+    out += &format!(
+        "Covered by more than {k}: {}\n",
+        list(coverage.covered_more_than(k))
+    );
+    out += "Covered by others:\n";
+    for (&(line, elf), assignment) in elves.iter().zip(&assignments) {
+        if coverage.covered_by_others(assignment) {
+            out += &format!("  Line {} elf {}: {assignment}\n", line + 1, elf + 1);
+        }
+    }
+    Ok(out)
+}
+
+// Draw the groups on the given lines (all if None) like the puzzle description
+fn draw(input: &str, lines: Option<Sections>) -> Result<String, Report> {
+    let groups: Vec<(usize, Vec<Sections>)> = parse(input)?
+        .into_iter()
+        .enumerate()
+        .filter(|&(n, _)| lines.is_none_or(|lines| lines.contains(n as u32 + 1)))
+        .collect();
+    let view = diagram::View::fit(groups.iter().flat_map(|(_, group)| group), 100);
+    let drawings: Vec<String> = groups
+        .iter()
+        .map(|(n, group)| format!("Line {}:\n{}", n + 1, view.render(group)))
+        .collect();
+    Ok(drawings.join("\n"))
+}
+
+// Usage: day4 [--report table|csv] [--camp k] [--draw] [--lines first-last] [input file]
 fn main() -> Result<(), Report> {
     color_eyre::install()?;
-    let input = include_str!("../input");
-    let (p1, p2) = score(input)?;
+    let mut input = include_str!("../input").to_string();
+    let mut report_format = None;
+    let mut camp_k = None;
+    let mut drawing = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--report" => {
+                report_format = Some(
+                    args.next()
+                        .ok_or_else(|| eyre!("--report needs 'table' or 'csv'"))?,
+                )
+            }
+            "--camp" => {
+                camp_k = Some(
+                    args.next()
+                        .ok_or_else(|| eyre!("--camp needs a number of elves"))?
+                        .parse()?,
+                )
+            }
+            "--draw" => drawing = drawing.or(Some(None)),
+            // Drawing only some of the lines
+            "--lines" => {
+                let lines = args
+                    .next()
+                    .ok_or_else(|| eyre!("--lines needs a 'first-last' line range"))?;
+                drawing = Some(Some(parse_lines(&lines)?))
+            }
+            path => input = std::fs::read_to_string(path)?,
+        }
+    }
+
+    if let Some(lines) = drawing {
+        print!("{}", draw(&input, lines)?);
+        return Ok(());
+    }
+    if let Some(k) = camp_k {
+        print!("{}", camp_report(&input, k)?);
+        return Ok(());
+    }
+    if let Some(format) = report_format {
+        let reports: Vec<_> = parse(&input)?
+            .into_iter()
+            .map(report::GroupReport::new)
+            .collect();
+        match format.as_str() {
+            "table" => print!("{}", report::to_table(&reports)),
+            "csv" => print!("{}", report::to_csv(&reports)),
+            _ => return Err(eyre!("--report needs 'table' or 'csv'")),
+        }
+        return Ok(());
+    }
+
+    let (p1, p2) = score(&input)?;
     println!("Part 1: {}", p1);
     println!("Part 2: {}", p2);
     Ok(())
@@ -88,4 +212,64 @@ mod tests {
         assert_eq!(super::score(TEST_INPUT).unwrap(), (2, 4));
         assert_eq!(super::score(INPUT).unwrap(), (518, 909));
     }
+
+    #[test]
+    fn large_section_ids() {
+        assert_eq!(super::score("200-400,300-350").unwrap(), (1, 1));
+        assert_eq!(super::score("1000-2000,2001-4000").unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn groups_of_elves() {
+        assert_eq!(super::score("1-2,8-9,3-5").unwrap(), (0, 0));
+        // 1-2 and 8-9 are both inside 1-9
+        assert_eq!(super::score("1-2,8-9,1-9").unwrap(), (1, 1));
+        assert_eq!(super::score("1-2,8-9,2-3").unwrap(), (0, 1));
+        assert_eq!(super::score("1-2,3-4,5-6,7-8,8-9").unwrap(), (0, 1));
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let error = |line| {
+            super::score(&format!("2-4,6-8\n{line}"))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("9-3,4-5"),
+            "Line 2: assignment '9-3' ends before it starts"
+        );
+        assert_eq!(
+            error("2-4,6-8xyz"),
+            "Line 2: '6-8xyz' is not a 'start-end' assignment"
+        );
+        assert_eq!(
+            error("12-14"),
+            "Line 2: expected at least two assignments in '12-14'"
+        );
+        assert_eq!(
+            error("12-14,6-8,"),
+            "Line 2: '' is not a 'start-end' assignment"
+        );
+        assert_eq!(
+            error("1-99999999999,2-3"),
+            "Line 2: section ID '99999999999' in '1-99999999999' is too large"
+        );
+    }
+
+    #[test]
+    fn line_ranges() {
+        let error = |lines| super::parse_lines(lines).unwrap_err().to_string();
+        assert_eq!(
+            error("sample-input.txt"),
+            "'sample-input.txt' is not a 'first-last' line range"
+        );
+        assert_eq!(error("0-2"), "'0-2' is not a 'first-last' line range");
+        assert_eq!(error("3-2"), "line range '3-2' ends before it starts");
+        let lines = super::parse_lines("3-3").unwrap();
+        assert_eq!(
+            super::draw(TEST_INPUT, Some(lines)).unwrap(),
+            "Line 3:\n....567.. 5-7\n......789 7-9\n......^..\n"
+        );
+    }
 }
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+
+use crate::camp::Coverage;
+use crate::Sections;
+
+#[derive(Debug, PartialEq)]
+pub struct GroupReport {
+    pub assignments: Vec<Sections>,
+    // Sections more than one elf in the group cleans
+    pub overlap: Vec<Sections>,
+    // Elves (by index) whose sections are all cleaned by the rest of the
+    // group, picked one at a time so the group still covers everything
+    pub redundant_elves: Vec<usize>,
+}
+
+impl GroupReport {
+    pub fn new(assignments: Vec<Sections>) -> Self {
+        let overlap = Coverage::new(&assignments).covered_more_than(1);
+
+        let mut remaining = assignments.clone();
+        let mut redundant_elves = vec![];
+        for (elf, assignment) in assignments.iter().enumerate() {
+            if Coverage::new(&remaining).covered_by_others(assignment) {
+                let at = remaining.iter().position(|a| a == assignment).unwrap();
+                remaining.remove(at);
+                redundant_elves.push(elf);
+            }
+        }
+
+        GroupReport {
+            assignments,
+            overlap,
+            redundant_elves,
+        }
+    }
+
+    pub fn overlap_len(&self) -> u128 {
+        self.overlap.iter().map(|o| o.len()).sum()
+    }
+
+    // Sections nobody has to clean twice if the redundant elves go elsewhere
+    pub fn saved(&self) -> u128 {
+        self.redundant_elves
+            .iter()
+            .map(|&elf| self.assignments[elf].len())
+            .sum()
+    }
+
+    fn sizes(&self) -> Vec<String> {
+        self.assignments
+            .iter()
+            .map(|a| a.len().to_string())
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Totals {
+    pub groups: usize,
+    pub sections: u128,
+    pub overlap: u128,
+    pub redundant_elves: usize,
+    pub saved: u128,
+}
+
+pub fn totals(reports: &[GroupReport]) -> Totals {
+    let mut totals = Totals::default();
+    for r in reports {
+        totals.groups += 1;
+        totals.sections += r.assignments.iter().map(|a| a.len()).sum::<u128>();
+        totals.overlap += r.overlap_len();
+        totals.redundant_elves += r.redundant_elves.len();
+        totals.saved += r.saved();
+    }
+    totals
+}
+
+fn join<T: ToString>(items: &[T], separator: &str) -> String {
+    if items.is_empty() {
+        return "-".to_string();
+    }
+    let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+    items.join(separator)
+}
+
+fn elves(r: &GroupReport) -> Vec<usize> {
+    r.redundant_elves.iter().map(|elf| elf + 1).collect()
+}
+
+pub fn to_table(reports: &[GroupReport]) -> String {
+    let mut out = format!(
+        "{:>5} {:>23} {:>11} {:>7} {:>11} {:>9} {:>5}\n",
+        "Line", "Assignments", "Sizes", "Overlap", "Redundant", "Reassign", "Saved"
+    );
+    for (i, r) in reports.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>5} {:>23} {:>11} {:>7} {:>11} {:>9} {:>5}",
+            i + 1,
+            join(&r.assignments, ","),
+            join(&r.sizes(), ","),
+            r.overlap_len(),
+            join(&r.overlap, ","),
+            join(&elves(r), ","),
+            r.saved()
+        )
+        .unwrap();
+    }
+    let t = totals(reports);
+    writeln!(
+        out,
+        "Total: {} groups, {} sections assigned, {} overlapping, {} redundant elves, {} sections saved",
+        t.groups, t.sections, t.overlap, t.redundant_elves, t.saved
+    )
+    .unwrap();
+    out
+}
+
+// Lists inside a field are separated by spaces
+pub fn to_csv(reports: &[GroupReport]) -> String {
+    let mut out = String::from("line,assignments,sizes,overlap,redundant,reassign,saved\n");
+    for (i, r) in reports.iter().enumerate() {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            i + 1,
+            join(&r.assignments, " "),
+            join(&r.sizes(), " "),
+            r.overlap_len(),
+            join(&r.overlap, " "),
+            join(&elves(r), " "),
+            r.saved()
+        )
+        .unwrap();
+    }
+    let t = totals(reports);
+    writeln!(
+        out,
+        "total,,{},{},,{},{}",
+        t.sections, t.overlap, t.redundant_elves, t.saved
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::Interval;
+
+    #[test]
+    fn group_reports() {
+        let s = |a, b| Interval::new(a, b).unwrap();
+        let reports = vec![
+            GroupReport::new(vec![s(2, 8), s(3, 7)]),
+            GroupReport::new(vec![s(5, 7), s(7, 9)]),
+            GroupReport::new(vec![s(2, 4), s(6, 8)]),
+            // Either copy could go, but not both
+            GroupReport::new(vec![s(1, 3), s(1, 3), s(2, 5)]),
+        ];
+        assert_eq!(reports[0].saved(), 5);
+        assert_eq!(reports[1].overlap, vec![s(7, 7)]);
+        assert_eq!(reports[1].saved(), 0);
+        assert_eq!(reports[2].overlap_len(), 0);
+        assert_eq!(reports[3].redundant_elves, vec![0]);
+        assert_eq!(reports[3].overlap, vec![s(1, 3)]);
+        assert_eq!(
+            totals(&reports[..3]),
+            Totals {
+                groups: 3,
+                sections: 7 + 5 + 3 + 3 + 3 + 3,
+                overlap: 6,
+                redundant_elves: 1,
+                saved: 5
+            }
+        );
+        assert_eq!(
+            to_csv(&reports).lines().nth(1),
+            Some("1,2-8 3-7,7 5,5,3-7,2,5")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use color_eyre::eyre::{eyre, Report};
+
+use crate::{Crane, CraneModel::*, Instruction, Piles};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PileStats {
+    pub moves_in: usize,
+    pub moves_out: usize,
+    pub crates_in: usize,
+    pub crates_out: usize,
+    pub peak: usize,
+    // Line of the instruction that first took the pile to its peak, none if
+    // it was there from the start
+    pub peak_line: Option<usize>,
+}
+
+pub struct Analysis {
+    pub piles: Vec<PileStats>,
+    pub moves: usize,
+    pub compressed: Vec<Instruction>,
+}
+
+// The single move doing the same as `a` followed by `b`, Some(None) if they
+// cancel out, None if they can't be combined for this crane
+fn combine(a: Instruction, b: Instruction, crane: &Crane) -> Option<Option<Instruction>> {
+    let towards =
+        |quantity, of: Instruction| Some((quantity > 0).then_some(Instruction { quantity, ..of }));
+    let same_way = a.src == b.src && a.dst == b.dst;
+    let back = a.src == b.dst && a.dst == b.src;
+    match crane.model {
+        // Moving one crate at a time, so only the total going each way matters
+        CrateMover9000 if same_way => towards(a.quantity + b.quantity, a),
+        CrateMover9000 if back && a.quantity >= b.quantity => towards(a.quantity - b.quantity, a),
+        CrateMover9000 if back => towards(b.quantity - a.quantity, b),
+        // Putting back a whole stack lifted in one go. Taking back part of it
+        // would leave its bottom crates, which no single move does.
+        CrateMover9001
+            if back
+                && b.quantity == a.quantity
+                && crane.capacity.is_none_or(|capacity| a.quantity <= capacity) =>
+        {
+            Some(None)
+        }
+        // A single crate doesn't get turned
+        _ if back && a.quantity == 1 && b.quantity == 1 => Some(None),
+        _ => None,
+    }
+}
+
+// An equivalent procedure with moves of nothing dropped and neighbouring
+// moves merged or cancelled out where the crane allows it
+pub fn compress(instructions: &[Instruction], crane: &Crane) -> Vec<Instruction> {
+    let mut compressed: Vec<Instruction> = vec![];
+    for &ins in instructions {
+        if ins.quantity == 0 {
+            continue;
+        }
+        let Some(&last) = compressed.last() else {
+            compressed.push(ins);
+            continue;
+        };
+        match combine(last, ins, crane) {
+            Some(combined) => {
+                compressed.pop();
+                compressed.extend(combined);
+            }
+            None => compressed.push(ins),
+        }
+    }
+    compressed
+}
+
+// Carries out the procedure, keeping track of what happens to each pile
+pub fn analyse(
+    piles: &Piles,
+    instructions: &[(usize, Instruction)],
+    crane: &Crane,
+) -> Result<Analysis, Report> {
+    let mut stats: Vec<PileStats> = piles
+        .0
+        .iter()
+        .map(|pile| PileStats {
+            peak: pile.len(),
+            ..PileStats::default()
+        })
+        .collect();
+
+    let mut state = piles.clone();
+    for &(n, ins) in instructions {
+        state
+            .apply(ins, crane)
+            .map_err(|e| eyre!("Line {n}: '{ins}': {e}"))?;
+        stats[ins.src].moves_out += 1;
+        stats[ins.src].crates_out += ins.quantity;
+        let dst = &mut stats[ins.dst];
+        dst.moves_in += 1;
+        dst.crates_in += ins.quantity;
+        if state.0[ins.dst].len() > dst.peak {
+            dst.peak = state.0[ins.dst].len();
+            dst.peak_line = Some(n);
+        }
+    }
+
+    let procedure: Vec<Instruction> = instructions.iter().map(|&(_, ins)| ins).collect();
+    Ok(Analysis {
+        piles: stats,
+        moves: procedure.len(),
+        compressed: compress(&procedure, crane),
+    })
+}
+
+pub fn to_text(analysis: &Analysis) -> String {
+    let mut out = format!(
+        "{:>4} {:>8} {:>9} {:>9} {:>10} {:>4} {:>9}\n",
+        "Pile", "Moves in", "Moves out", "Crates in", "Crates out", "Peak", "Peak line"
+    );
+    for (i, pile) in analysis.piles.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>4} {:>8} {:>9} {:>9} {:>10} {:>4} {:>9}",
+            i + 1,
+            pile.moves_in,
+            pile.moves_out,
+            pile.crates_in,
+            pile.crates_out,
+            pile.peak,
+            pile.peak_line
+                .map_or("start".to_string(), |n| n.to_string())
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "\n{} moves, compressed to {}:",
+        analysis.moves,
+        analysis.compressed.len()
+    )
+    .unwrap();
+    for ins in &analysis.compressed {
+        writeln!(out, "{ins}").unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    fn ins(quantity: usize, src: usize, dst: usize) -> Instruction {
+        Instruction { quantity, src, dst }
+    }
+
+    fn text(moves: Vec<Instruction>) -> String {
+        let moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+        moves.join(", ")
+    }
+
+    #[test]
+    fn compressed_moves() {
+        let procedure = [
+            ins(2, 0, 1),
+            ins(0, 2, 1),
+            ins(1, 0, 1),
+            ins(3, 1, 0),
+            ins(1, 1, 2),
+            ins(1, 2, 1),
+        ];
+        assert_eq!(text(compress(&procedure, &CrateMover9000.into())), "");
+        assert_eq!(
+            text(compress(&procedure, &CrateMover9001.into())),
+            "move 2 from 1 to 2, move 1 from 1 to 2, move 3 from 2 to 1"
+        );
+        // 2 crates are lifted as 1 and 1, so taking back 1 isn't the same
+        let crane = Crane {
+            capacity: Some(1),
+            ..CrateMover9001.into()
+        };
+        assert_eq!(
+            text(compress(&procedure[..2], &crane)),
+            "move 2 from 1 to 2"
+        );
+        assert_eq!(
+            text(compress(&[ins(2, 0, 1), ins(1, 1, 0)], &crane)),
+            "move 2 from 1 to 2, move 1 from 2 to 1"
+        );
+        assert_eq!(
+            text(compress(
+                &[ins(2, 0, 1), ins(2, 1, 0), ins(1, 0, 1), ins(2, 1, 0)],
+                &CrateMover9001.into()
+            )),
+            "move 1 from 1 to 2, move 2 from 2 to 1"
+        );
+    }
+
+    #[test]
+    fn same_final_piles() {
+        for input in [
+            include_str!("../test_input.txt"),
+            include_str!("../input.txt"),
+        ] {
+            let (piles, instructions) = parse_input(input).unwrap();
+            for model in [CrateMover9000, CrateMover9001, Rotating] {
+                let crane = model.into();
+                let analysis = analyse(&piles, &instructions, &crane).unwrap();
+                let (mut end, mut compressed_end) = (piles.clone(), piles.clone());
+                for &(_, ins) in &instructions {
+                    end.apply(ins, &crane).unwrap();
+                }
+                for &ins in &analysis.compressed {
+                    compressed_end.apply(ins, &crane).unwrap();
+                }
+                assert_eq!(compressed_end, end);
+            }
+        }
+    }
+
+    #[test]
+    fn pile_stats() {
+        let (piles, instructions) = parse_input(include_str!("../test_input.txt")).unwrap();
+        let analysis = analyse(&piles, &instructions, &CrateMover9000.into()).unwrap();
+        assert_eq!(
+            analysis.piles[2],
+            PileStats {
+                moves_in: 1,
+                moves_out: 0,
+                crates_in: 3,
+                crates_out: 0,
+                peak: 4,
+                peak_line: Some(7),
+            }
+        );
+        assert_eq!(analysis.piles[1].peak_line, None);
+        // moving M and C to pile 1 and M back is moving just C
+        assert_eq!(
+            text(analysis.compressed),
+            "move 1 from 2 to 1, move 3 from 1 to 3, move 1 from 2 to 1"
+        );
+    }
+}
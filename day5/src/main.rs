@@ -1,27 +1,86 @@
-#![feature(get_many_mut)]
-
 // Heavily inspired by https://fasterthanli.me/series/advent-of-code-2022/part-5
 
+mod analysis;
+mod solve;
+mod trace;
+mod undo;
+
 use crate::CraneModel::*;
 
 use std::fmt;
+use std::ops::Range;
+use std::time::Duration;
 
+use color_eyre::eyre::{eyre, Report};
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take},
-    combinator::{all_consuming, map},
-    multi::separated_list1,
-    sequence::{delimited, preceded, tuple},
-    Finish, IResult,
+    bytes::complete::{tag, take_till1},
+    character::complete::space0,
+    combinator::{all_consuming, consumed, map, map_opt},
+    multi::many1,
+    sequence::{delimited, preceded, terminated, tuple},
+    Finish, IResult, Offset,
 };
 
+#[derive(Clone, Copy)]
 enum CraneModel {
     CrateMover9000,
     CrateMover9001,
+    // Lifts like the 9001 but spins the lifted stack, so the top crate is
+    // put down at the bottom
+    Rotating,
 }
 
-#[derive(Clone, Copy)]
-struct Crate(char);
+// A crane model and what its operator has to respect
+#[derive(Clone)]
+struct Crane {
+    model: CraneModel,
+    // Most crates lifted at once, bigger moves are done in batches off the top
+    capacity: Option<usize>,
+    // Highest each pile may get, or a single limit for every pile
+    height_limits: Vec<usize>,
+}
+
+impl Crane {
+    fn height_limit(&self, pile: usize) -> Option<usize> {
+        match self.height_limits[..] {
+            [limit] => Some(limit),
+            _ => self.height_limits.get(pile).copied(),
+        }
+    }
+
+    fn check_heights(&self, piles: &Piles) -> Result<(), Report> {
+        let limits = self.height_limits.len();
+        if limits > 1 && limits != piles.0.len() {
+            return Err(eyre!(
+                "there are {limits} height limits for {} piles, give one for all or one for each",
+                piles.0.len()
+            ));
+        }
+        for (i, pile) in piles.0.iter().enumerate() {
+            if let Some(limit) = self.height_limit(i).filter(|&limit| pile.len() > limit) {
+                return Err(eyre!(
+                    "pile {} starts {} crates high, its limit is {limit}",
+                    i + 1,
+                    pile.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<CraneModel> for Crane {
+    fn from(model: CraneModel) -> Self {
+        Crane {
+            model,
+            capacity: None,
+            height_limits: vec![],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Crate(String);
 
 impl fmt::Debug for Crate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -29,6 +88,7 @@ impl fmt::Debug for Crate {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Hash)]
 struct Piles(Vec<Vec<Crate>>);
 
 impl fmt::Debug for Piles {
@@ -39,53 +99,255 @@ impl fmt::Debug for Piles {
         Ok(())
     }
 }
+
+// The puzzle's drawing: a row of crates per height, top first, then the
+// pile numbers. Holes are kept as spaces so every row has the same width, and
+// every column is as wide as the widest crate or pile number.
+impl fmt::Display for Piles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let height = self.0.iter().map(Vec::len).max().unwrap_or(0);
+        let width = self
+            .0
+            .iter()
+            .flatten()
+            .map(|c| c.0.chars().count() + 2)
+            .chain([3, self.0.len().to_string().len()])
+            .max()
+            .unwrap();
+        for row in (0..height).rev() {
+            let cells: Vec<String> = self
+                .0
+                .iter()
+                .map(|pile| {
+                    let cell = pile
+                        .get(row)
+                        .map_or(String::new(), |c| format!("[{}]", c.0));
+                    format!("{cell:<width$}")
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        let numbers: Vec<String> = (1..=self.0.len()).map(|n| format!("{n:^width$}")).collect();
+        writeln!(f, "{}", numbers.join(" "))
+    }
+}
+
+// Why an instruction can't be carried out on the piles
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    SamePile(usize),
+    NoSuchPile {
+        pile: usize,
+        piles: usize,
+    },
+    NotEnoughCrates {
+        pile: usize,
+        quantity: usize,
+        len: usize,
+    },
+    TooHigh {
+        pile: usize,
+        height: usize,
+        limit: usize,
+    },
+}
+
+// Pile numbers are shown 1-indexed, as in the puzzle input
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::SamePile(pile) => {
+                write!(f, "can't move crates from pile {} onto itself", pile + 1)
+            }
+            MoveError::NoSuchPile { pile, piles } => {
+                write!(f, "there is no pile {}, only {piles}", pile + 1)
+            }
+            MoveError::NotEnoughCrates {
+                pile,
+                quantity,
+                len,
+            } => write!(
+                f,
+                "can't take {quantity} crates from pile {}, it only has {len}",
+                pile + 1
+            ),
+            MoveError::TooHigh {
+                pile,
+                height,
+                limit,
+            } => write!(
+                f,
+                "pile {} would be {height} crates high, its limit is {limit}",
+                pile + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
 impl Piles {
-    fn apply(&mut self, ins: Instruction, crane_model: &CraneModel) {
+    // The label of the top crate of each pile, none for an empty pile
+    fn tops(&self) -> Vec<Option<&str>> {
+        self.0
+            .iter()
+            .map(|pile| pile.last().map(|c| c.0.as_str()))
+            .collect()
+    }
+
+    // Checks the instruction first, leaving the piles untouched if it fails
+    fn apply(&mut self, ins: Instruction, crane: &Crane) -> Result<(), MoveError> {
+        let piles = self.0.len();
+        for pile in [ins.src, ins.dst] {
+            if pile >= piles {
+                return Err(MoveError::NoSuchPile { pile, piles });
+            }
+        }
+        if ins.src == ins.dst {
+            return Err(MoveError::SamePile(ins.src));
+        }
+        let len = self.0[ins.src].len();
+        if ins.quantity > len {
+            return Err(MoveError::NotEnoughCrates {
+                pile: ins.src,
+                quantity: ins.quantity,
+                len,
+            });
+        }
+        let height = self.0[ins.dst].len() + ins.quantity;
+        if let Some(limit) = crane.height_limit(ins.dst).filter(|&limit| height > limit) {
+            return Err(MoveError::TooHigh {
+                pile: ins.dst,
+                height,
+                limit,
+            });
+        }
+
         let [src, dst] = self
             .0
-            .get_many_mut([ins.src, ins.dst])
-            .expect("out of bounds / overlapping src/dst stacks");
+            .get_disjoint_mut([ins.src, ins.dst])
+            .expect("src and dst were checked above");
 
-        match crane_model {
-            CrateMover9000 => dst.extend(src.drain((src.len() - ins.quantity)..).rev()),
-            CrateMover9001 => dst.extend(src.drain((src.len() - ins.quantity)..)),
+        let mut left = ins.quantity;
+        while left > 0 {
+            let batch = crane.capacity.map_or(left, |capacity| left.min(capacity));
+            let lifted = src.drain((src.len() - batch)..);
+            match crane.model {
+                CrateMover9000 => dst.extend(lifted.rev()),
+                CrateMover9001 => dst.extend(lifted),
+                Rotating => {
+                    let mut lifted: Vec<_> = lifted.collect();
+                    lifted.rotate_right(1);
+                    dst.extend(lifted);
+                }
+            }
+            left -= batch;
         }
+        Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 struct Instruction {
     quantity: usize,
     src: usize,
     dst: usize,
 }
 
+// Back in the puzzle's 1-indexed form
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.quantity,
+            self.src + 1,
+            self.dst + 1
+        )
+    }
+}
+
+// A crate label is anything up to the closing bracket, "[AB]" is crate AB
 fn parse_crate(i: &str) -> IResult<&str, Crate> {
-    let first_char = |s: &str| Crate(s.chars().next().unwrap());
-    let f = delimited(tag("["), take(1_usize), tag("]"));
-    map(f, first_char)(i)
+    let label = take_till1(|c: char| c == ']' || c.is_whitespace());
+    let f = delimited(tag("["), label, tag("]"));
+    map(f, |s: &str| Crate(s.to_string()))(i)
 }
 
-fn parse_hole(i: &str) -> IResult<&str, ()> {
-    // `drop` takes a value and returns nothing, which is perfect for our case
-    map(tag("   "), drop)(i)
+// Columns (in characters, not bytes) each item on a line of the drawing
+// takes up. Items are separated by any number of spaces, and trailing
+// spaces are optional.
+fn columns<'a, T>(
+    line: &'a str,
+    item: impl FnMut(&'a str) -> IResult<&'a str, T>,
+) -> IResult<&'a str, Vec<(Range<usize>, T)>> {
+    terminated(
+        many1(preceded(
+            space0,
+            map(consumed(item), |(s, item)| {
+                let start = line[..line.offset(s)].chars().count();
+                (start..start + s.chars().count(), item)
+            }),
+        )),
+        space0,
+    )(line)
 }
 
-fn parse_crate_or_hole(i: &str) -> IResult<&str, Option<Crate>> {
-    alt((map(parse_crate, Some), map(parse_hole, |_| None)))(i)
+fn parse_crate_line(line: &str) -> IResult<&str, Vec<(Range<usize>, Crate)>> {
+    columns(line, parse_crate)
 }
 
-fn parse_crate_line(i: &str) -> IResult<&str, Vec<Option<Crate>>> {
-    separated_list1(tag(" "), parse_crate_or_hole)(i)
+fn parse_number_line(line: &str) -> IResult<&str, Vec<(Range<usize>, usize)>> {
+    columns(line, parse_number)
+}
+
+// The top crates as in the puzzle answer, a space for an empty pile. Longer
+// labels would run together, so then each is in brackets as in the drawing,
+// separated by spaces, with [] for an empty pile.
+fn tops_text(tops: &[Option<&str>]) -> String {
+    if tops
+        .iter()
+        .flatten()
+        .all(|label| label.chars().count() == 1)
+    {
+        tops.iter().map(|top| top.unwrap_or(" ")).collect()
+    } else {
+        let tops: Vec<String> = tops
+            .iter()
+            .map(|top| format!("[{}]", top.unwrap_or_default()))
+            .collect();
+        tops.join(" ")
+    }
+}
+
+// Either form of top crates tops_text writes
+fn parse_tops(text: &str) -> Result<Vec<Option<String>>, Report> {
+    if !text.trim_start().starts_with('[') {
+        return Ok(text
+            .chars()
+            .map(|c| (c != ' ').then(|| c.to_string()))
+            .collect());
+    }
+    text.split_whitespace()
+        .map(
+            |top| match top.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+                Some("") => Ok(None),
+                Some(label) if !label.contains(['[', ']']) => Ok(Some(label.to_string())),
+                _ => Err(eyre!(
+                    "'{top}' is not a crate like '[A]' or '[]' for no crate"
+                )),
+            },
+        )
+        .collect()
 }
 
 fn parse_number(i: &str) -> IResult<&str, usize> {
     map(nom::character::complete::u32, |n| n as _)(i)
 }
 
-// convert from 1-indexed to 0-indexed
+// convert from 1-indexed to 0-indexed, there is no pile 0
 fn parse_pile_number(i: &str) -> IResult<&str, usize> {
-    map(parse_number, |i| i - 1)(i)
+    map_opt(parse_number, |i: usize| i.checked_sub(1))(i)
 }
 
 // Parse lines like "move # from # to #"
@@ -100,60 +362,276 @@ fn parse_instruction(i: &str) -> IResult<&str, Instruction> {
     )(i)
 }
 
-// Transpose rows to columns
-fn transpose_rev<T>(v: Vec<Vec<Option<T>>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            let mut v = Vec::with_capacity(256); // just to be on the safe side
-            v.extend(iters.iter_mut().rev().filter_map(|n| n.next().unwrap()));
-            v
-        })
-        .collect()
+// The crate lines at the start of the input and the number line below them.
+// Each crate goes on the pile whose number is under it.
+fn parse_drawing<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Result<(Piles, usize), Report> {
+    let mut rows = vec![];
+    let numbers = loop {
+        let n = rows.len() + 1;
+        let line = lines
+            .next()
+            .ok_or_else(|| eyre!("the drawing has no line of pile numbers"))?;
+        if let Ok((_, crates)) = all_consuming(parse_crate_line)(line).finish() {
+            rows.push(crates);
+        } else if let Ok((_, numbers)) = all_consuming(parse_number_line)(line).finish() {
+            if !numbers
+                .iter()
+                .map(|(_, number)| *number)
+                .eq(1..=numbers.len())
+            {
+                return Err(eyre!("Line {n}: piles should be numbered from 1 in order"));
+            }
+            break numbers;
+        } else {
+            return Err(eyre!(
+                "Line {n}: '{line}' is neither crates nor pile numbers"
+            ));
+        }
+    };
+
+    let mut piles = vec![vec![]; numbers.len()];
+    let top_row = rows.len();
+    for (height, row) in rows.into_iter().rev().enumerate() {
+        let n = top_row - height;
+        for (columns, c) in row {
+            let pile = numbers
+                .iter()
+                .position(|(under, _)| under.start < columns.end && columns.start < under.end)
+                .ok_or_else(|| eyre!("Line {n}: crate [{c:?}] isn't above a pile number"))?;
+            if piles[pile].len() != height {
+                return Err(eyre!(
+                    "Line {n}: crate [{c:?}] doesn't sit on top of pile {}",
+                    pile + 1
+                ));
+            }
+            piles[pile].push(c);
+        }
+    }
+    Ok((Piles(piles), top_row + 1))
 }
 
-fn process_input(input: &str, crane_model: CraneModel) -> String {
+// The starting piles and the instructions, with their line numbers
+fn parse_input(input: &str) -> Result<(Piles, Vec<(usize, Instruction)>), Report> {
     let mut lines = input.lines();
+    let (piles, drawing_lines) = parse_drawing(&mut lines)?;
 
-    let crate_lines: Vec<_> = lines
-        .by_ref()
-        .map_while(|line| {
-            all_consuming(parse_crate_line)(line)
+    // line numbers in errors count from the top of the input, past the
+    // drawing and the empty line after it
+    let first_instruction = drawing_lines + 2;
+
+    // we've consumed the "numbers line" but not the separating line
+    if lines.next() != Some("") {
+        return Err(eyre!("expected an empty line after the drawing"));
+    }
+
+    let instructions = lines
+        .enumerate()
+        .map(|(n, line)| {
+            let n = n + first_instruction;
+            all_consuming(parse_instruction)(line)
                 .finish()
-                .ok()
-                .map(|(_, line)| line)
+                .map(|(_, ins)| (n, ins))
+                .map_err(|_| eyre!("Line {n}: '{line}' is not a 'move # from # to #' instruction"))
         })
-        .collect();
-
-    let mut piles = Piles(transpose_rev(crate_lines));
+        .collect::<Result<_, _>>()?;
+    Ok((piles, instructions))
+}
 
-    // we've consumed the "numbers line" but not the separating line
-    assert!(lines.next().unwrap().is_empty());
+// The top crate of each pile, or a space for an empty pile. With a trace,
+// also records the starting piles and the piles after every instruction.
+fn process_input(
+    input: &str,
+    crane: impl Into<Crane>,
+    mut trace: Option<&mut Vec<trace::Step>>,
+) -> Result<String, Report> {
+    let crane = crane.into();
+    let (mut piles, instructions) = parse_input(input)?;
+    crane.check_heights(&piles)?;
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.push(trace::Step {
+            instruction: None,
+            piles: piles.clone(),
+        });
+    }
 
-    for ins in lines.map(|line| all_consuming(parse_instruction)(line).finish().unwrap().1) {
-        piles.apply(ins, &crane_model);
+    for (n, ins) in instructions {
+        piles
+            .apply(ins, &crane)
+            .map_err(|e| eyre!("Line {n}: '{ins}': {e}"))?;
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.push(trace::Step {
+                instruction: Some((n, ins)),
+                piles: piles.clone(),
+            });
+        }
     }
+    Ok(tops_text(&piles.tops()))
+}
 
-    // return the top crate from each pile
-    piles
-        .0
-        .iter()
-        .map(|pile| pile.last().unwrap().0)
-        .collect::<String>()
+// What to do with the procedure instead of printing both parts
+enum Mode {
+    Analyse,
+    Undo,
+    Trace,
+    Step(usize),
+    Animate(Duration),
 }
 
-fn main() {
-    const INPUT: &str = include_str!("../input.txt");
+// Usage: day5 [--crane 9000|9001|rotating] [--capacity n] [--height-limits h[,h...]]
+//             [--trace | --step n | --animate [ms] | --undo | --analyse]
+//             [--solve tops | --solve-drawing file] [--max-states n] [--max-moves n]
+//             [input file]
+// A crane given only a capacity or height limits is a CrateMover 9000. With no
+// crane options at all, both parts are solved.
+fn main() -> Result<(), Report> {
+    color_eyre::install()?;
+    let mut input = include_str!("../input.txt").to_string();
+    let mut model = None;
+    let mut capacity = None;
+    let mut height_limits = None;
+    let mut mode = None;
+    let mut target = None;
+    let mut limits = solve::Limits::default();
 
-    println!("Part 1: {}", process_input(INPUT, CrateMover9000));
-    println!("Part 2: {}", process_input(INPUT, CrateMover9001));
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crane" => {
+                model = match args.next().as_deref() {
+                    Some("9000") => Some(CrateMover9000),
+                    Some("9001") => Some(CrateMover9001),
+                    Some("rotating") => Some(Rotating),
+                    _ => return Err(eyre!("--crane needs '9000', '9001' or 'rotating'")),
+                };
+            }
+            "--capacity" => {
+                capacity = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| eyre!("--capacity needs a number of crates above 0"))?,
+                );
+            }
+            "--height-limits" => {
+                height_limits = Some(
+                    args.next()
+                        .ok_or_else(|| eyre!("--height-limits needs comma separated heights"))?
+                        .split(',')
+                        .map(|h| h.parse())
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+            "--analyse" => mode = Some(Mode::Analyse),
+            "--undo" => mode = Some(Mode::Undo),
+            "--solve" => {
+                let tops = args
+                    .next()
+                    .ok_or_else(|| eyre!("--solve needs the top crates"))?;
+                target = Some(solve::Target::Tops(parse_tops(&tops)?));
+            }
+            "--solve-drawing" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| eyre!("--solve-drawing needs a file"))?;
+                let drawing = std::fs::read_to_string(path)?;
+                let (piles, _) = parse_drawing(&mut drawing.lines())?;
+                target = Some(solve::Target::Piles(piles));
+            }
+            "--max-states" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| eyre!("--max-states needs a number"))?;
+                limits.max_states = n.parse()?;
+            }
+            "--max-moves" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| eyre!("--max-moves needs a number"))?;
+                limits.max_moves = Some(n.parse()?);
+            }
+            "--trace" => mode = Some(Mode::Trace),
+            "--step" => {
+                let n = args
+                    .next()
+                    .ok_or_else(|| eyre!("--step needs a step number"))?;
+                mode = Some(Mode::Step(n.parse()?));
+            }
+            "--animate" => {
+                // a delay in milliseconds is optional
+                let ms = match args.next_if(|a| a.parse::<u64>().is_ok()) {
+                    Some(ms) => ms.parse()?,
+                    None => 300,
+                };
+                mode = Some(Mode::Animate(Duration::from_millis(ms)));
+            }
+            path => input = std::fs::read_to_string(path)?,
+        }
+    }
+    let crane = (model.is_some() || capacity.is_some() || height_limits.is_some()).then(|| Crane {
+        model: model.unwrap_or(CrateMover9000),
+        capacity,
+        height_limits: height_limits.unwrap_or_default(),
+    });
+
+    // a puzzle input going from the starting piles to the target, ignoring
+    // the input's own procedure
+    if let Some(target) = target {
+        let crane = crane.unwrap_or_else(|| Crane::from(CrateMover9000));
+        let (piles, _) = parse_input(&input)?;
+        crane.check_heights(&piles)?;
+        let procedure = solve::solve(&piles, &target, &crane, &limits)?;
+        println!("{piles}");
+        for ins in procedure {
+            println!("{ins}");
+        }
+        return Ok(());
+    }
+
+    let Some(mode) = mode else {
+        match crane {
+            Some(crane) => println!("Top crates: {}", process_input(&input, crane, None)?),
+            None => {
+                println!("Part 1: {}", process_input(&input, CrateMover9000, None)?);
+                println!("Part 2: {}", process_input(&input, CrateMover9001, None)?);
+            }
+        }
+        return Ok(());
+    };
+    let crane = crane.unwrap_or_else(|| Crane::from(CrateMover9000));
+    let mut steps = vec![];
+    process_input(&input, crane.clone(), Some(&mut steps))?;
+    match mode {
+        Mode::Analyse => {
+            let (piles, instructions) = parse_input(&input)?;
+            let analysis = analysis::analyse(&piles, &instructions, &crane)?;
+            print!("{}", analysis::to_text(&analysis));
+        }
+        // a puzzle input going from the final piles back to the start
+        Mode::Undo => {
+            let (piles, instructions) = parse_input(&input)?;
+            let instructions: Vec<_> = instructions.into_iter().map(|(_, ins)| ins).collect();
+            let (end, inverse) = undo::undo(&piles, &instructions, &crane)?;
+            println!("{end}");
+            for ins in inverse {
+                println!("{ins}");
+            }
+        }
+        Mode::Trace => print!("{}", trace::to_text(&steps)),
+        Mode::Step(n) => {
+            let step = steps.get(n).ok_or_else(|| {
+                eyre!("there is no step {n}, the last one is {}", steps.len() - 1)
+            })?;
+            print!("Step {n}, {step}");
+        }
+        Mode::Animate(delay) => trace::animate(&steps, delay)?,
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const INPUT: &str = include_str!("../input.txt");
     const TEST_INPUT: &str = include_str!("../test_input.txt");
@@ -161,13 +639,190 @@ mod tests {
     #[test]
     fn part_1() {
         // grab one crate at a time
-        assert_eq!(process_input(TEST_INPUT, CrateMover9000), "CMZ");
-        assert_eq!(process_input(INPUT, CrateMover9000), "VQZNJMWTR");
+        assert_eq!(
+            process_input(TEST_INPUT, CrateMover9000, None).unwrap(),
+            "CMZ"
+        );
+        assert_eq!(
+            process_input(INPUT, CrateMover9000, None).unwrap(),
+            "VQZNJMWTR"
+        );
     }
     #[test]
     fn part_2() {
         // grab many crates simultaneously
-        assert_eq!(process_input(TEST_INPUT, CrateMover9001), "MCD");
-        assert_eq!(process_input(INPUT, CrateMover9001), "NLCDCLVMQ");
+        assert_eq!(
+            process_input(TEST_INPUT, CrateMover9001, None).unwrap(),
+            "MCD"
+        );
+        assert_eq!(
+            process_input(INPUT, CrateMover9001, None).unwrap(),
+            "NLCDCLVMQ"
+        );
+    }
+
+    #[test]
+    fn other_cranes() {
+        let crane = |model, capacity, height_limits| Crane {
+            model,
+            capacity,
+            height_limits,
+        };
+        // lifting 2 at a time, "move 3 from 1 to 3" puts Z on top of N and D
+        let top = |crane| process_input(TEST_INPUT, crane, None).unwrap();
+        assert_eq!(top(crane(CrateMover9001, Some(2), vec![])), "MCZ");
+        // a capacity of 1 is the same as a 9000
+        assert_eq!(top(crane(CrateMover9001, Some(1), vec![])), "CMZ");
+        // "move 3 from 1 to 3" puts down D, Z, N
+        assert_eq!(top(crane(Rotating, None, vec![])), "CMN");
+        assert_eq!(top(crane(CrateMover9000, None, vec![4])), "CMZ");
+
+        let error = |crane| {
+            process_input(TEST_INPUT, crane, None)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(crane(CrateMover9000, None, vec![9, 9, 3])),
+            "Line 7: 'move 3 from 1 to 3': pile 3 would be 4 crates high, its limit is 3"
+        );
+        assert_eq!(
+            error(crane(CrateMover9000, None, vec![9, 2, 9])),
+            "pile 2 starts 3 crates high, its limit is 2"
+        );
+        for limits in [vec![9, 9], vec![9, 9, 3, 1, 1]] {
+            let count = limits.len();
+            assert_eq!(
+                error(crane(CrateMover9000, None, limits)),
+                format!(
+                    "there are {count} height limits for 3 piles, give one for all or one for each"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn drawing() {
+        let drawing: String = TEST_INPUT
+            .lines()
+            .take(4)
+            .map(|l| l.to_owned() + "\n")
+            .collect();
+        let piles = parse_drawing(&mut drawing.lines()).unwrap().0;
+        assert_eq!(piles.to_string(), drawing);
+
+        // trailing spaces are optional
+        let mut trimmed = drawing.lines().map(str::trim_end);
+        assert_eq!(parse_drawing(&mut trimmed).unwrap().0, piles);
+    }
+
+    #[test]
+    fn wide_drawings() {
+        let drawing = "
+[AB]
+[C]  [DEF]                                           [G]
+ 1     2    3    4    5    6    7    8    9    10    11";
+        let (piles, lines) = parse_drawing(&mut drawing.lines().skip(1)).unwrap();
+        assert_eq!(lines, 3);
+        let label = |pile: usize| format!("{:?}", piles.0[pile]);
+        assert_eq!(label(0), "[C, AB]");
+        assert_eq!(label(1), "[DEF]");
+        assert_eq!(label(10), "[G]");
+        assert_eq!(
+            tops_text(&piles.tops()),
+            "[AB] [DEF] [] [] [] [] [] [] [] [] [G]"
+        );
+        let tops: Vec<Option<String>> = piles.tops().iter().map(|t| t.map(String::from)).collect();
+        assert_eq!(parse_tops(&tops_text(&piles.tops())).unwrap(), tops);
+        assert!(piles
+            .to_string()
+            .ends_with("  1     2     3     4     5     6     7     8     9    10    11  \n"));
+
+        let error = |drawing: &str| parse_drawing(&mut drawing.lines()).unwrap_err().to_string();
+        assert_eq!(
+            error("[A] [B]\n 1   3 "),
+            "Line 2: piles should be numbered from 1 in order"
+        );
+        assert_eq!(
+            error("[A] [B]     [C]\n 1   2 "),
+            "Line 1: crate [C] isn't above a pile number"
+        );
+        assert_eq!(
+            error("[A]\n    [B]\n 1   2 "),
+            "Line 1: crate [A] doesn't sit on top of pile 1"
+        );
+        assert_eq!(
+            error("[A] [B]\n[C] [D]"),
+            "the drawing has no line of pile numbers"
+        );
+        assert_eq!(
+            error("[A] [B\n 1   2 "),
+            "Line 1: '[A] [B' is neither crates nor pile numbers"
+        );
+    }
+
+    fn piles() -> impl Strategy<Value = Vec<Vec<String>>> {
+        prop::collection::vec(prop::collection::vec("[A-Z0-9]{1,3}", 0..6), 1..15)
+    }
+
+    proptest! {
+        #[test]
+        fn drawing_round_trip(piles in piles()) {
+            let piles = Piles(piles.into_iter().map(|p| p.into_iter().map(Crate).collect()).collect());
+            let drawing = piles.to_string();
+            prop_assert_eq!(parse_drawing(&mut drawing.lines()).unwrap().0, piles);
+        }
+    }
+
+    #[test]
+    fn bad_moves() {
+        let error = |line| {
+            let input = format!("{TEST_INPUT}{line}\n");
+            process_input(&input, CrateMover9000, None)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("move 1 from 2 to 2"),
+            "Line 10: 'move 1 from 2 to 2': can't move crates from pile 2 onto itself"
+        );
+        assert_eq!(
+            error("move 1 from 4 to 1"),
+            "Line 10: 'move 1 from 4 to 1': there is no pile 4, only 3"
+        );
+        assert_eq!(
+            error("move 1 from 0 to 1"),
+            "Line 10: 'move 1 from 0 to 1' is not a 'move # from # to #' instruction"
+        );
+        // pile 3 has 4 crates after the example procedure
+        assert_eq!(
+            error("move 5 from 3 to 1"),
+            "Line 10: 'move 5 from 3 to 1': can't take 5 crates from pile 3, it only has 4"
+        );
+        assert_eq!(
+            error("move one from 3 to 1"),
+            "Line 10: 'move one from 3 to 1' is not a 'move # from # to #' instruction"
+        );
+    }
+
+    #[test]
+    fn empty_piles() {
+        let mut piles = Piles(vec![vec![Crate("A".to_string())], vec![]]);
+        let ins = Instruction {
+            quantity: 1,
+            src: 1,
+            dst: 0,
+        };
+        assert_eq!(
+            piles.apply(ins, &CrateMover9001.into()),
+            Err(MoveError::NotEnoughCrates {
+                pile: 1,
+                quantity: 1,
+                len: 0
+            })
+        );
+        // the emptied pile shows as a space
+        let input = format!("{TEST_INPUT}move 1 from 1 to 2\n");
+        assert_eq!(process_input(&input, CrateMover9001, None).unwrap(), " MD");
     }
 }
@@ -0,0 +1,270 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use color_eyre::eyre::{eyre, Report};
+
+use crate::{Crane, Instruction, Piles};
+
+// What the piles should look like at the end
+pub enum Target {
+    // The label of the top crate of each pile, none for an empty pile
+    Tops(Vec<Option<String>>),
+    Piles(Piles),
+}
+
+impl Target {
+    // Piles that don't look like the target yet
+    fn mismatches(&self, piles: &Piles) -> usize {
+        match self {
+            Target::Tops(tops) => piles
+                .tops()
+                .into_iter()
+                .zip(tops)
+                .filter(|(top, wanted)| *top != wanted.as_deref())
+                .count(),
+            Target::Piles(target) => piles
+                .0
+                .iter()
+                .zip(&target.0)
+                .filter(|(a, b)| a != b)
+                .count(),
+        }
+    }
+
+    // A move changes two piles, so it fixes at most two mismatches. Changes
+    // by at most one per move, which A* needs to never reopen a state.
+    fn lower_bound(&self, piles: &Piles) -> usize {
+        self.mismatches(piles).div_ceil(2)
+    }
+
+    // Rule out targets no procedure can reach before searching
+    fn check(&self, piles: &Piles, crane: &Crane) -> Result<(), Report> {
+        fn crates(piles: &Piles) -> Vec<&str> {
+            let mut crates: Vec<&str> = piles.0.iter().flatten().map(|c| c.0.as_str()).collect();
+            crates.sort_unstable();
+            crates
+        }
+        let piles_in_target = match self {
+            Target::Tops(tops) => tops.len(),
+            Target::Piles(target) => target.0.len(),
+        };
+        if piles_in_target != piles.0.len() {
+            return Err(eyre!(
+                "the target has {piles_in_target} piles, the drawing {}",
+                piles.0.len()
+            ));
+        }
+        let available = crates(piles);
+        match self {
+            Target::Tops(tops) => {
+                let mut wanted: Vec<&str> = tops.iter().flatten().map(String::as_str).collect();
+                wanted.sort_unstable();
+                let mut available = available.iter();
+                for c in wanted {
+                    // both are sorted, so skip the crates that can't match
+                    if available.find(|&&a| a >= c) != Some(&c) {
+                        return Err(eyre!("there are not enough '{c}' crates for the target"));
+                    }
+                }
+            }
+            Target::Piles(target) => {
+                if crates(target) != available {
+                    return Err(eyre!("the target doesn't have the same crates"));
+                }
+                crane.check_heights(target)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Limits {
+    // Different states reached before giving up, which bounds the memory used
+    pub max_states: usize,
+    pub max_moves: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_states: 100_000,
+            max_moves: None,
+        }
+    }
+}
+
+// Every instruction the crane can carry out on the piles
+fn moves<'a>(
+    piles: &'a Piles,
+    crane: &'a Crane,
+) -> impl Iterator<Item = (Instruction, Piles)> + 'a {
+    let n = piles.0.len();
+    (0..n)
+        .flat_map(move |src| (0..n).map(move |dst| (src, dst)))
+        .filter(|(src, dst)| src != dst)
+        .flat_map(move |(src, dst)| {
+            (1..=piles.0[src].len()).map(move |quantity| Instruction { quantity, src, dst })
+        })
+        .filter_map(move |ins| {
+            let mut next = piles.clone();
+            next.apply(ins, crane).ok().map(|_| (ins, next))
+        })
+}
+
+// A shortest procedure reaching the target, found with A*. The lower bound
+// never overestimates, so the first time the target comes off the queue no
+// shorter procedure exists.
+pub fn solve(
+    piles: &Piles,
+    target: &Target,
+    crane: &Crane,
+    limits: &Limits,
+) -> Result<Vec<Instruction>, Report> {
+    target.check(piles, crane)?;
+
+    // every state seen, with the move and state it was first reached from
+    let mut states: Vec<(Piles, Option<(usize, Instruction)>)> = vec![(piles.clone(), None)];
+    let mut moves_to: HashMap<Piles, usize> = HashMap::from([(piles.clone(), 0)]);
+    let mut queue = BinaryHeap::from([Reverse((target.lower_bound(piles), 0, 0))]);
+
+    while let Some(Reverse((_, moves_so_far, id))) = queue.pop() {
+        if moves_to[&states[id].0] < moves_so_far {
+            continue;
+        }
+        if target.mismatches(&states[id].0) == 0 {
+            let mut procedure = vec![];
+            let mut at = id;
+            while let Some((from, ins)) = states[at].1 {
+                procedure.push(ins);
+                at = from;
+            }
+            procedure.reverse();
+            return Ok(procedure);
+        }
+        if limits.max_moves.is_some_and(|max| moves_so_far >= max) {
+            continue;
+        }
+
+        let current = states[id].0.clone();
+        for (ins, next) in moves(&current, crane) {
+            let moves_next = moves_so_far + 1;
+            if moves_to.get(&next).is_some_and(|&m| m <= moves_next) {
+                continue;
+            }
+            let bound = moves_next + target.lower_bound(&next);
+            if limits.max_moves.is_some_and(|max| bound > max) {
+                continue;
+            }
+            if moves_to.insert(next.clone(), moves_next).is_none()
+                && moves_to.len() > limits.max_states
+            {
+                return Err(eyre!("gave up after reaching {} states", limits.max_states));
+            }
+            states.push((next, Some((id, ins))));
+            queue.push(Reverse((bound, moves_next, states.len() - 1)));
+        }
+    }
+    Err(eyre!("no procedure reaches the target"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_drawing, parse_input, parse_tops, CraneModel::*};
+
+    const TEST_INPUT: &str = include_str!("../test_input.txt");
+
+    fn tops(text: &str) -> Target {
+        Target::Tops(parse_tops(text).unwrap())
+    }
+
+    fn check_solution(target: &Target, crane: &Crane, moves: usize) {
+        let (piles, _) = parse_input(TEST_INPUT).unwrap();
+        check_solution_from(piles, target, crane, moves);
+    }
+
+    fn check_solution_from(piles: Piles, target: &Target, crane: &Crane, moves: usize) {
+        let procedure = solve(&piles, target, crane, &Limits::default()).unwrap();
+        assert_eq!(procedure.len(), moves);
+        let mut end = piles;
+        for ins in procedure {
+            end.apply(ins, crane).unwrap();
+        }
+        assert_eq!(target.mismatches(&end), 0);
+    }
+
+    #[test]
+    fn shortest_procedures() {
+        let crane = CrateMover9000.into();
+        // the puzzle's procedure takes 4 moves, 2 are enough
+        check_solution(&tops("CMZ"), &crane, 2);
+        check_solution(&tops("NDP"), &crane, 0);
+        // everything onto pile 1, P last
+        check_solution(&tops("P  "), &crane, 2);
+
+        let end = "        [Z]
+        [N]
+        [D]
+[C] [M] [P]
+ 1   2   3 ";
+        // the puzzle also moves M to pile 1 and back, taking 4 moves
+        let target = Target::Piles(parse_drawing(&mut end.lines()).unwrap().0);
+        check_solution(&target, &crane, 3);
+        // keeping the order of lifted crates makes it harder to turn Z N D over
+        check_solution(&target, &CrateMover9001.into(), 4);
+    }
+
+    #[test]
+    fn wide_labels() {
+        let drawing = "[AB]
+[C]  [DEF]
+ 1     2    3";
+        let piles = parse_drawing(&mut drawing.lines()).unwrap().0;
+        let crane = CrateMover9000.into();
+        check_solution_from(piles.clone(), &tops("[C] [] [AB]"), &crane, 2);
+        // AB has to wait on pile 3 until DEF is out of the way
+        check_solution_from(piles.clone(), &tops("[DEF] [AB] [C]"), &crane, 4);
+        let error = solve(&piles, &tops("[AB] [C]"), &crane, &Limits::default()).unwrap_err();
+        assert_eq!(error.to_string(), "the target has 2 piles, the drawing 3");
+        let error = solve(&piles, &tops("[] [] [A]"), &crane, &Limits::default()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "there are not enough 'A' crates for the target"
+        );
+    }
+
+    #[test]
+    fn unreachable_targets() {
+        let (piles, _) = parse_input(TEST_INPUT).unwrap();
+        let error = |target: &str, crane: &Crane, limits: &Limits| {
+            solve(&piles, &tops(target), crane, limits)
+                .unwrap_err()
+                .to_string()
+        };
+        let crane = CrateMover9000.into();
+        assert_eq!(
+            error("XMZ", &crane, &Limits::default()),
+            "there are not enough 'X' crates for the target"
+        );
+        assert_eq!(
+            error("CM", &crane, &Limits::default()),
+            "the target has 2 piles, the drawing 3"
+        );
+        let limits = Limits {
+            max_moves: Some(1),
+            ..Limits::default()
+        };
+        assert_eq!(
+            error("CMZ", &crane, &limits),
+            "no procedure reaches the target"
+        );
+        let limits = Limits {
+            max_states: 2,
+            ..Limits::default()
+        };
+        assert_eq!(
+            error("P  ", &crane, &limits),
+            "gave up after reaching 2 states"
+        );
+    }
+}
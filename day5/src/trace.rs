@@ -0,0 +1,83 @@
+use std::fmt;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Instruction, Piles};
+
+// A state of the piles, and the instruction and its input line that led to it
+// (none for the starting drawing)
+pub struct Step {
+    pub instruction: Option<(usize, Instruction)>,
+    pub piles: Piles,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.instruction {
+            Some((line, ins)) => writeln!(f, "Line {line}: {ins}")?,
+            None => writeln!(f, "Start")?,
+        }
+        write!(f, "{}", self.piles)
+    }
+}
+
+pub fn to_text(trace: &[Step]) -> String {
+    let steps: Vec<String> = trace
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("Step {i}, {step}"))
+        .collect();
+    steps.join("\n")
+}
+
+// Redraw the piles in place after every step. The drawing is padded to the
+// tallest state so the pile numbers stay on the same terminal line.
+pub fn animate(trace: &[Step], delay: Duration) -> std::io::Result<()> {
+    let height = trace
+        .iter()
+        .flat_map(|step| step.piles.0.iter().map(Vec::len))
+        .max()
+        .unwrap_or(0);
+    let mut out = std::io::stdout();
+    for (i, step) in trace.iter().enumerate() {
+        let step = format!("Step {i}, {step}");
+        let padding = "\n".repeat(height + 2 - step.lines().count().min(height + 2));
+        // clear the screen and go back to the top left corner
+        write!(out, "\x1b[2J\x1b[H{padding}{step}")?;
+        out.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process_input, CraneModel::CrateMover9000};
+
+    #[test]
+    fn puzzle_steps() {
+        let mut trace = vec![];
+        let tops = process_input(
+            include_str!("../test_input.txt"),
+            CrateMover9000,
+            Some(&mut trace),
+        )
+        .unwrap();
+        assert_eq!(trace.len(), 5);
+        assert_eq!(tops, "CMZ");
+        // the second step in the puzzle description
+        assert_eq!(
+            trace[2].to_string(),
+            "Line 7: move 3 from 1 to 3
+        [Z]
+        [N]
+    [C] [D]
+    [M] [P]
+ 1   2   3 
+"
+        );
+        assert!(to_text(&trace).starts_with("Step 0, Start\n    [D]    \n"));
+    }
+}
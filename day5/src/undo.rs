@@ -0,0 +1,138 @@
+use color_eyre::eyre::{eyre, Report};
+
+use crate::{Crane, CraneModel::*, Instruction, Piles};
+
+// Sizes of the batches the crane lifts one after the other for a move
+fn batches(quantity: usize, crane: &Crane) -> Vec<usize> {
+    let capacity = crane.capacity.unwrap_or(quantity).max(1);
+    (0..quantity)
+        .step_by(capacity)
+        .map(|moved| capacity.min(quantity - moved))
+        .collect()
+}
+
+// Moves that take a batch of crates put down on `to` back onto `from`,
+// in the order they were in
+fn undo_batch(size: usize, from: usize, to: usize, crane: &Crane) -> Vec<Instruction> {
+    let back = Instruction {
+        quantity: size,
+        src: to,
+        dst: from,
+    };
+    let forth = Instruction {
+        quantity: size,
+        src: from,
+        dst: to,
+    };
+    match crane.model {
+        CrateMover9000 | CrateMover9001 => vec![back],
+        // Every move turns the batch once more, so keep moving it back and
+        // forth until it has turned a multiple of its size and is on `from`
+        Rotating => {
+            let moves = if size.is_multiple_of(2) {
+                size - 1
+            } else {
+                2 * size - 1
+            };
+            (0..moves)
+                .map(|i| if i % 2 == 0 { back } else { forth })
+                .collect()
+        }
+    }
+}
+
+// Instructions taking the piles after the procedure back to how they were.
+// No move lifts more than the crane's capacity, so the crane never splits them.
+pub fn inverse(instructions: &[Instruction], crane: &Crane) -> Vec<Instruction> {
+    instructions
+        .iter()
+        .rev()
+        .flat_map(|ins| {
+            batches(ins.quantity, crane)
+                .into_iter()
+                .rev()
+                .flat_map(move |size| undo_batch(size, ins.src, ins.dst, crane))
+        })
+        .collect()
+}
+
+// The piles after the procedure and its inverse, checked by carrying out
+// both. Fails when the procedure or the inverse breaks the crane's limits.
+pub fn undo(
+    piles: &Piles,
+    instructions: &[Instruction],
+    crane: &Crane,
+) -> Result<(Piles, Vec<Instruction>), Report> {
+    let mut state = piles.clone();
+    for &ins in instructions {
+        state.apply(ins, crane).map_err(|e| eyre!("'{ins}': {e}"))?;
+    }
+    let end = state.clone();
+
+    let inverse = inverse(instructions, crane);
+    for &ins in &inverse {
+        state
+            .apply(ins, crane)
+            .map_err(|e| eyre!("undoing with '{ins}': {e}"))?;
+    }
+    if state != *piles {
+        return Err(eyre!("the inverse procedure doesn't restore the piles"));
+    }
+    Ok((end, inverse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn restores_the_piles() {
+        for input in [
+            include_str!("../test_input.txt"),
+            include_str!("../input.txt"),
+        ] {
+            let (piles, instructions) = parse_input(input).unwrap();
+            let instructions: Vec<_> = instructions.into_iter().map(|(_, ins)| ins).collect();
+            for model in [CrateMover9000, CrateMover9001, Rotating] {
+                for capacity in [None, Some(1), Some(2), Some(5)] {
+                    let crane = Crane {
+                        model,
+                        capacity,
+                        height_limits: vec![],
+                    };
+                    undo(&piles, &instructions, &crane).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_moves() {
+        let ins = |quantity, src, dst| Instruction { quantity, src, dst };
+        let procedure = [ins(1, 1, 0), ins(3, 0, 2)];
+        let crane = |model, capacity| Crane {
+            model,
+            capacity,
+            height_limits: vec![],
+        };
+        let text = |moves: Vec<Instruction>| {
+            let moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+            moves.join(", ")
+        };
+        assert_eq!(
+            text(inverse(&procedure, &crane(CrateMover9001, None))),
+            "move 3 from 3 to 1, move 1 from 1 to 2"
+        );
+        // the last batch of a move is the first to go back
+        assert_eq!(
+            text(inverse(&procedure, &crane(CrateMover9001, Some(2)))),
+            "move 1 from 3 to 1, move 2 from 3 to 1, move 1 from 1 to 2"
+        );
+        assert_eq!(
+            text(inverse(&procedure[1..], &crane(Rotating, None))),
+            "move 3 from 3 to 1, move 3 from 1 to 3, move 3 from 3 to 1, \
+             move 3 from 1 to 3, move 3 from 3 to 1"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Only part of the module is benchmarked, and its tests don't run here
+#[path = "../src/marker.rs"]
+#[allow(dead_code, unused_imports)]
+mod marker;
+
+use marker::{find_marker, segments, slide, Segmentation};
+
+// The implementation the sliding window replaced, a new set for every window
+mod naive {
+    use std::collections::HashSet;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    pub fn decode(input: &str, marker_size: usize) -> usize {
+        input
+            .graphemes(true)
+            .collect::<Vec<&str>>()
+            .windows(marker_size)
+            .position(|window| window.iter().collect::<HashSet<_>>().len() == marker_size)
+            .map(|pos| pos + marker_size)
+            .unwrap_or_default()
+    }
+}
+
+// xorshift, good enough to scatter characters
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// A stream of random characters from too small an alphabet to hold a marker,
+// so the only marker is at the very end
+fn generate(len: usize, marker_size: usize) -> String {
+    let alphabet: Vec<char> = ('a'..).take(marker_size).collect();
+    let mut rng = Rng(0x2022_1206);
+    let mut out: String = (0..len)
+        .map(|_| alphabet[(rng.next() % (marker_size as u64 - 1)) as usize])
+        .collect();
+    out.extend(&alphabet);
+    out
+}
+
+fn bench(c: &mut Criterion) {
+    let len = 100_000;
+    for marker_size in [4, 14, 64, 256] {
+        let input = generate(len, marker_size);
+        // the sliding count window over graphemes, which find_marker skips
+        // for ASCII input
+        let sliding = |input: &str| {
+            let bytes = input.as_bytes();
+            let graphemes = || segments(bytes, Segmentation::Graphemes);
+            slide(graphemes(), graphemes(), marker_size).map(|p| p.index)
+        };
+        let fast = |input: &str| find_marker(input, marker_size, Segmentation::Graphemes);
+        assert_eq!(sliding(&input), Some(len + marker_size));
+        assert_eq!(fast(&input).map(|p| p.index), Some(len + marker_size));
+        assert_eq!(naive::decode(&input, marker_size), len + marker_size);
+
+        let mut group = c.benchmark_group(format!("marker size {marker_size}"));
+        group.sample_size(10);
+        group.bench_with_input(BenchmarkId::new("naive", len), &input, |b, i| {
+            b.iter(|| naive::decode(black_box(i), marker_size))
+        });
+        group.bench_with_input(BenchmarkId::new("sliding", len), &input, |b, i| {
+            b.iter(|| sliding(black_box(i)))
+        });
+        // the same as sliding beyond ASCII
+        if input.is_ascii() {
+            group.bench_with_input(BenchmarkId::new("ascii", len), &input, |b, i| {
+                b.iter(|| fast(black_box(i)))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
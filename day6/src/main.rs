@@ -1,22 +1,73 @@
-use std::collections::HashSet;
-use unicode_segmentation::UnicodeSegmentation;
+mod marker;
+mod stream;
+
+use std::fs::File;
+use std::io::{self, Read};
+
+use marker::{find_marker, Position, Segmentation};
+use stream::{Decoder, Kind};
 
 const INPUT: &str = include_str!("../input.txt");
 
-fn main() {
-    println!("Part 1: {}", decode(INPUT, 4));
-    println!("Part 2: {}", decode(INPUT, 14));
+// Where a marker ends in segments, and in bytes when that's different
+fn show(position: Position) -> String {
+    if position.index == position.offset {
+        position.index.to_string()
+    } else {
+        format!("{} (byte {})", position.index, position.offset)
+    }
 }
 
-// Return the string index after marker_size consecutive unique characters
-fn decode(input: &str, marker_size: usize) -> usize {
-    input
-        .graphemes(true)
-        .collect::<Vec<&str>>()
-        .windows(marker_size)
-        .position(|window| window.iter().collect::<HashSet<_>>().len() == marker_size)
-        .map(|pos| pos + marker_size)
-        .unwrap_or_default()
+// Usage: day6 [--all] [--segments bytes|chars|graphemes] [input file]
+fn main() -> io::Result<()> {
+    let mut all = false;
+    let mut segmentation = Segmentation::Graphemes;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => all = true,
+            "--segments" => {
+                segmentation = match args.next().as_deref() {
+                    Some("bytes") => Segmentation::Bytes,
+                    Some("chars") => Segmentation::Chars,
+                    Some("graphemes") => Segmentation::Graphemes,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "--segments takes bytes, chars or graphemes",
+                        ))
+                    }
+                }
+            }
+            _ => path = Some(arg),
+        }
+    }
+
+    // Every marker, reading the stream a chunk at a time
+    if all {
+        let reader: Box<dyn Read> = match path {
+            Some(path) => Box::new(File::open(path)?),
+            None => Box::new(INPUT.as_bytes()),
+        };
+        let kinds = [Kind::StartOfPacket, Kind::StartOfMessage];
+        for marker in Decoder::new(reader, &kinds, segmentation) {
+            let marker = marker?;
+            println!("{} marker ends at {}", marker.kind, show(marker.position));
+        }
+        return Ok(());
+    }
+
+    let input = match path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => INPUT.to_string(),
+    };
+    let part = |marker_size| {
+        find_marker(&input, marker_size, segmentation).map_or("no marker".to_string(), show)
+    };
+    println!("Part 1: {}", part(4));
+    println!("Part 2: {}", part(14));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -25,27 +76,37 @@ mod tests {
 
     const INPUT: &str = include_str!("../input.txt");
 
+    fn decode(input: &str, marker_size: usize) -> Option<usize> {
+        find_marker(input, marker_size, Segmentation::Graphemes).map(|position| position.index)
+    }
+
     #[test]
     fn part1() {
-        assert_eq!(decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7);
-        assert_eq!(decode("åjqjpqågbljsphdztnvjfqwrcgsålb", 4), 7);
-        assert_eq!(decode("🦀jqjpq🦀gbljsphdztnvjfqwrcgs🦀lb", 4), 7);
-        assert_eq!(decode("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5);
-        assert_eq!(decode("nppdvjthqldpwncqszvftbrmjlhg", 4), 6);
-        assert_eq!(decode("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), 10);
-        assert_eq!(decode("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
-        assert_eq!(decode(INPUT, 4), 1287);
+        assert_eq!(decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), Some(7));
+        assert_eq!(decode("åjqjpqågbljsphdztnvjfqwrcgsålb", 4), Some(7));
+        assert_eq!(decode("🦀jqjpq🦀gbljsphdztnvjfqwrcgs🦀lb", 4), Some(7));
+        assert_eq!(decode("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), Some(5));
+        assert_eq!(decode("nppdvjthqldpwncqszvftbrmjlhg", 4), Some(6));
+        assert_eq!(decode("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), Some(10));
+        assert_eq!(decode("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), Some(11));
+        assert_eq!(decode(INPUT, 4), Some(1287));
     }
 
     #[test]
     fn part2() {
-        assert_eq!(decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
-        assert_eq!(decode("åjqjpqågbljsphdztnvjfqwrcgsålb", 14), 19);
-        assert_eq!(decode("🦀jqjpq🦀gbljsphdztnvjfqwrcgs🦀lb", 14), 19);
-        assert_eq!(decode("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), 23);
-        assert_eq!(decode("nppdvjthqldpwncqszvftbrmjlhg", 14), 23);
-        assert_eq!(decode("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), 29);
-        assert_eq!(decode("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), 26);
-        assert_eq!(decode(INPUT, 14), 3716);
+        assert_eq!(decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), Some(19));
+        assert_eq!(decode("åjqjpqågbljsphdztnvjfqwrcgsålb", 14), Some(19));
+        assert_eq!(decode("🦀jqjpq🦀gbljsphdztnvjfqwrcgs🦀lb", 14), Some(19));
+        assert_eq!(decode("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), Some(23));
+        assert_eq!(decode("nppdvjthqldpwncqszvftbrmjlhg", 14), Some(23));
+        assert_eq!(decode("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), Some(29));
+        assert_eq!(decode("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), Some(26));
+        assert_eq!(decode(INPUT, 14), Some(3716));
+    }
+
+    #[test]
+    fn no_marker() {
+        assert_eq!(decode("abcabcabc", 4), None);
+        assert_eq!(decode("", 14), None);
     }
 }
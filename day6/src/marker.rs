@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use unicode_segmentation::UnicodeSegmentation;
+
+// How many times each grapheme is in the window, and how many are different
+pub struct Window<K> {
+    counts: HashMap<K, usize>,
+    distinct: usize,
+}
+
+impl<K: Hash + Eq> Window<K> {
+    pub fn new() -> Self {
+        Window {
+            counts: HashMap::new(),
+            distinct: 0,
+        }
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    pub fn add(&mut self, grapheme: K) {
+        let count = self.counts.entry(grapheme).or_default();
+        if *count == 0 {
+            self.distinct += 1;
+        }
+        *count += 1;
+    }
+
+    // Counts of zero are kept, so the map only grows with the alphabet
+    pub fn remove(&mut self, grapheme: &K) {
+        let count = self.counts.get_mut(grapheme).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.distinct -= 1;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segmentation {
+    Bytes,
+    // Unicode scalar values
+    Chars,
+    // Extended grapheme clusters, what a reader sees as one character
+    Graphemes,
+}
+
+// Where a marker ends, counted in segments and in bytes from the start
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub index: usize,
+    pub offset: usize,
+}
+
+// The input split up, each segment as its bytes. Unless split into bytes the
+// input has to be valid UTF-8.
+pub fn segments(input: &[u8], segmentation: Segmentation) -> Box<dyn Iterator<Item = &[u8]> + '_> {
+    let text = || std::str::from_utf8(input).expect("segmenting text that isn't UTF-8");
+    match segmentation {
+        Segmentation::Bytes => Box::new(input.chunks(1)),
+        Segmentation::Chars => Box::new(
+            text()
+                .char_indices()
+                .map(move |(i, c)| &input[i..i + c.len_utf8()]),
+        ),
+        Segmentation::Graphemes => Box::new(text().graphemes(true).map(str::as_bytes)),
+    }
+}
+
+// The window slides one segment at a time, with the second iterator over the
+// same segments trailing marker_size behind to drop the one falling out of it
+pub fn slide<'a>(
+    segments: impl Iterator<Item = &'a [u8]>,
+    mut leaving: impl Iterator<Item = &'a [u8]>,
+    marker_size: usize,
+) -> Option<Position> {
+    let mut window = Window::new();
+    let mut offset = 0;
+    for (i, segment) in segments.enumerate() {
+        offset += segment.len();
+        window.add(segment);
+        if i >= marker_size {
+            window.remove(&leaving.next().unwrap());
+        }
+        if window.distinct() == marker_size {
+            return Some(Position {
+                index: i + 1,
+                offset,
+            });
+        }
+    }
+    None
+}
+
+// The same when every segment is a single byte, counting in an array
+fn slide_bytes(bytes: &[u8], marker_size: usize) -> Option<Position> {
+    let mut counts = [0_usize; 256];
+    let mut distinct = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if counts[byte as usize] == 0 {
+            distinct += 1;
+        }
+        counts[byte as usize] += 1;
+        if i >= marker_size {
+            let left = bytes[i - marker_size] as usize;
+            counts[left] -= 1;
+            if counts[left] == 0 {
+                distinct -= 1;
+            }
+        }
+        if distinct == marker_size {
+            return Some(Position {
+                index: i + 1,
+                offset: i + 1,
+            });
+        }
+    }
+    None
+}
+
+// Where the first marker_size consecutive unique segments end, None if there
+// are none. ASCII is one byte per character and per grapheme, except for
+// "\r\n" which is a single grapheme.
+pub fn find_marker(
+    input: &str,
+    marker_size: usize,
+    segmentation: Segmentation,
+) -> Option<Position> {
+    let bytewise = match segmentation {
+        Segmentation::Bytes => true,
+        Segmentation::Chars => input.is_ascii(),
+        Segmentation::Graphemes => input.is_ascii() && !input.contains("\r\n"),
+    };
+    if bytewise {
+        return slide_bytes(input.as_bytes(), marker_size);
+    }
+    let bytes = input.as_bytes();
+    slide(
+        segments(bytes, segmentation),
+        segments(bytes, segmentation),
+        marker_size,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmentations() {
+        let find = |input, segmentation| {
+            let position = find_marker(input, 4, segmentation).unwrap();
+            (position.index, position.offset)
+        };
+        let input = "åjqjpqågbljsphdztnvjfqwrcgsålb";
+        // å is two bytes, which count as two different segments
+        assert_eq!(find(input, Segmentation::Bytes), (4, 4));
+        assert_eq!(find(input, Segmentation::Chars), (7, 9));
+        assert_eq!(find(input, Segmentation::Graphemes), (7, 9));
+
+        let input = "🇳🇴jqjpq🇳🇴gbljsphdztnvjfqwrcgs🇳🇴lb";
+        // the flag is two characters of four bytes each
+        assert_eq!(find(input, Segmentation::Chars), (4, 10));
+        assert_eq!(find(input, Segmentation::Graphemes), (7, 21));
+
+        let input = "a\r\nbcd";
+        assert_eq!(find(input, Segmentation::Bytes), (4, 4));
+        assert_eq!(find(input, Segmentation::Chars), (4, 4));
+        assert_eq!(find(input, Segmentation::Graphemes), (4, 5));
+    }
+
+    #[test]
+    fn fast_path_agrees() {
+        let input = include_str!("../input.txt");
+        for marker_size in [4, 14] {
+            let fast = find_marker(input, marker_size, Segmentation::Chars);
+            let bytes = input.as_bytes();
+            let segmented = slide(
+                segments(bytes, Segmentation::Graphemes),
+                segments(bytes, Segmentation::Graphemes),
+                marker_size,
+            );
+            assert_eq!(fast, segmented);
+        }
+    }
+}
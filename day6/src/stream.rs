@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::marker::{segments, Position, Segmentation, Window};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl Kind {
+    pub fn size(self) -> usize {
+        match self {
+            Kind::StartOfPacket => 4,
+            Kind::StartOfMessage => 14,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::StartOfPacket => write!(f, "start-of-packet"),
+            Kind::StartOfMessage => write!(f, "start-of-message"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Marker {
+    pub kind: Kind,
+    pub position: Position,
+}
+
+// The last segments of the stream, as many as a marker of the kind has
+struct Tracker {
+    kind: Kind,
+    window: Window<Vec<u8>>,
+    recent: VecDeque<Vec<u8>>,
+}
+
+impl Tracker {
+    // Whether the segment completes a marker
+    fn push(&mut self, segment: &[u8]) -> bool {
+        self.window.add(segment.to_vec());
+        self.recent.push_back(segment.to_vec());
+        if self.recent.len() > self.kind.size() {
+            let left = self.recent.pop_front().unwrap();
+            self.window.remove(&left);
+        }
+        self.window.distinct() == self.kind.size()
+    }
+}
+
+const CHUNK_SIZE: usize = 8192;
+
+// Reads the stream a chunk at a time and yields every position where a marker
+// of one of the kinds ends, in order
+pub struct Decoder<R> {
+    reader: R,
+    segmentation: Segmentation,
+    // Bytes read but not segmented yet: an incomplete UTF-8 sequence or the
+    // last grapheme, which the next chunk could still add to
+    buffer: Vec<u8>,
+    trackers: Vec<Tracker>,
+    found: VecDeque<Marker>,
+    position: Position,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R, kinds: &[Kind], segmentation: Segmentation) -> Self {
+        let trackers = kinds
+            .iter()
+            .map(|&kind| Tracker {
+                kind,
+                window: Window::new(),
+                recent: VecDeque::with_capacity(kind.size() + 1),
+            })
+            .collect();
+        Decoder {
+            reader,
+            segmentation,
+            buffer: vec![],
+            trackers,
+            found: VecDeque::new(),
+            position: Position {
+                index: 0,
+                offset: 0,
+            },
+            done: false,
+        }
+    }
+
+    // How much of the buffer can be segmented. Bytes are all final, text
+    // stops before a cut off character and graphemes before the last one,
+    // unless it's the end of the stream.
+    fn ready(&self, end: bool) -> io::Result<usize> {
+        if self.segmentation == Segmentation::Bytes {
+            return Ok(self.buffer.len());
+        }
+        let valid = match std::str::from_utf8(&self.buffer) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() && !end => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.buffer[..valid]).unwrap();
+        Ok(match text.grapheme_indices(true).next_back() {
+            Some((last, _)) if self.segmentation == Segmentation::Graphemes && !end => last,
+            _ => valid,
+        })
+    }
+
+    // Feed the trackers what has been read, and at the end of the stream
+    // everything that's left
+    fn segment(&mut self, end: bool) -> io::Result<()> {
+        let ready = self.ready(end)?;
+        for segment in segments(&self.buffer[..ready], self.segmentation) {
+            self.position.index += 1;
+            self.position.offset += segment.len();
+            for tracker in &mut self.trackers {
+                if tracker.push(segment) {
+                    self.found.push_back(Marker {
+                        kind: tracker.kind,
+                        position: self.position,
+                    });
+                }
+            }
+        }
+        self.buffer.drain(..ready);
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(marker) = self.found.pop_front() {
+                return Some(Ok(marker));
+            }
+            if self.done {
+                return None;
+            }
+            let mut chunk = [0; CHUNK_SIZE];
+            let read = match self.reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            self.done = read == 0;
+            self.buffer.extend_from_slice(&chunk[..read]);
+            if let Err(e) = self.segment(self.done) {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where the first marker of the kind ends, None if the stream has none
+    fn first_marker(reader: impl Read, kind: Kind) -> io::Result<Option<usize>> {
+        let first = Decoder::new(reader, &[kind], Segmentation::Graphemes)
+            .next()
+            .transpose()?;
+        Ok(first.map(|marker| marker.position.index))
+    }
+
+    // Hands out a byte at a time, splitting every multi-byte character
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn split_input() {
+        for input in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "åjqjpqågbljsphdztnvjfqwrcgsålb",
+            "🦀jqjpq🦀gbljsphdztnvjfqwrcgs🦀lb",
+            // the flag is one grapheme made of two characters
+            "🇳🇴jqjpq🇳🇴gbljsphdztnvjfqwrcgs🇳🇴lb",
+        ] {
+            let reader = Trickle(input.as_bytes());
+            assert_eq!(first_marker(reader, Kind::StartOfPacket).unwrap(), Some(7));
+            let reader = Trickle(input.as_bytes());
+            assert_eq!(
+                first_marker(reader, Kind::StartOfMessage).unwrap(),
+                Some(19)
+            );
+        }
+    }
+
+    #[test]
+    fn every_marker() {
+        let kinds = [Kind::StartOfPacket, Kind::StartOfMessage];
+        let stream = "abcdeabcdefghijklmn".as_bytes();
+        let markers: Vec<(Kind, usize)> = Decoder::new(stream, &kinds, Segmentation::Bytes)
+            .map(|m| m.map(|m| (m.kind, m.position.index)))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        // every window of 4 is a start-of-packet marker, only the last 14
+        // are a start-of-message marker
+        let mut expected: Vec<(Kind, usize)> = (4..=19).map(|p| (Kind::StartOfPacket, p)).collect();
+        expected.push((Kind::StartOfMessage, 19));
+        assert_eq!(markers, expected);
+    }
+
+    #[test]
+    fn byte_offsets() {
+        let input = "🇳🇴jqjpq🇳🇴gbljsphdztnvjfqwrcgs🇳🇴lb";
+        let first = |segmentation| {
+            let reader = Trickle(input.as_bytes());
+            let mut decoder = Decoder::new(reader, &[Kind::StartOfPacket], segmentation);
+            decoder.next().unwrap().unwrap().position
+        };
+        let position = |index, offset| Position { index, offset };
+        assert_eq!(first(Segmentation::Bytes), position(4, 4));
+        assert_eq!(first(Segmentation::Chars), position(4, 10));
+        assert_eq!(first(Segmentation::Graphemes), position(7, 21));
+    }
+
+    #[test]
+    fn no_marker() {
+        assert_eq!(
+            first_marker("aabbaabb".as_bytes(), Kind::StartOfPacket).unwrap(),
+            None
+        );
+        assert_eq!(
+            first_marker("".as_bytes(), Kind::StartOfPacket).unwrap(),
+            None
+        );
+        let invalid = first_marker(&[b'a', 0xff, b'b'][..], Kind::StartOfPacket);
+        assert_eq!(invalid.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        // a character cut off at the end of the stream
+        let cut = first_marker(&"abcå".as_bytes()[..4], Kind::StartOfPacket);
+        assert_eq!(cut.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}